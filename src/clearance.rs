@@ -0,0 +1,262 @@
+use super::fill_rect::{FillRect, FillState};
+use super::polygon;
+
+// y range of the points within radius of the segment, for x in [min_x, max_x].
+// The capsule is convex, so the range is reached at the ends of the x range
+// or at its lowest and highest points.
+fn capsule_y_range(
+    p0: (f64, f64), p1: (f64, f64), radius: f64, min_x: f64, max_x: f64,
+) -> Option<(f64, f64)> {
+    let mut r: Option<(f64, f64)> = None;
+    let mut add = |chord: Option<(f64, f64)>| {
+        let Some((min_y, max_y)) = chord else { return };
+        r = Some(match r {
+            Some(b) => (b.0.min(min_y), b.1.max(max_y)),
+            None => (min_y, max_y),
+        });
+    };
+    add(polygon::capsule_chord(p0, p1, radius, min_x));
+    add(polygon::capsule_chord(p0, p1, radius, max_x));
+    for p in [p0, p1] {
+        if min_x <= p.0 && p.0 <= max_x {
+            add(Some((p.1 - radius, p.1 + radius)));
+        }
+    }
+    return r;
+}
+
+impl FillRect {
+    // Whether every point within clearance of the segment is filled, or
+    // every one is open. Outside of the work region counts as filled. Each
+    // column the segment passes is checked over the exact y range the
+    // clearance band covers in it.
+    pub fn segment_is_clear(
+        &self, p0: (f64, f64), p1: (f64, f64), clearance: f64, want_filled: bool,
+    ) -> bool {
+        let clearance = clearance.max(0.0);
+        let min_x = p0.0.min(p1.0) - clearance;
+        let max_x = p0.0.max(p1.0) + clearance;
+        let columns = if min_x < max_x {
+            self.map.data_range_clipped(min_x, max_x)
+        } else {
+            vec![(self.map.value(min_x).clone(), min_x, max_x)]
+        };
+
+        for (column, local_min_x, local_max_x) in columns {
+            let Some((min_y, max_y)) = capsule_y_range(p0, p1, clearance, local_min_x, local_max_x) else {
+                continue
+            };
+            let Some(column) = column else {
+                if want_filled { continue } else { return false }
+            };
+            let states = if min_y < max_y {
+                column.data_range_clipped(min_y, max_y)
+            } else {
+                vec![(*column.value(min_y), min_y, max_y)]
+            };
+            for (state, _, _) in states {
+                if (state == Some(FillState::Open)) == want_filled {
+                    return false;
+                }
+            }
+        }
+        return true;
+    }
+
+    // First point of the segment from p0 where a disc of radius clearance
+    // around it stops being in open area, None when the whole move is clear.
+    // Columns are walked in the direction of travel and every piece that is
+    // not open within the clearance band gives the exact point where the disc
+    // first touches it.
+    pub fn first_blocking_point(
+        &self, p0: (f64, f64), p1: (f64, f64), clearance: f64,
+    ) -> Option<(f64, f64)> {
+        if self.segment_is_clear(p0, p1, clearance, false) {
+            return None;
+        }
+        let clearance = clearance.max(0.0);
+        let min_x = p0.0.min(p1.0) - clearance;
+        let max_x = p0.0.max(p1.0) + clearance;
+        let mut columns = if min_x < max_x {
+            self.map.data_range_clipped(min_x, max_x)
+        } else {
+            vec![(self.map.value(min_x).clone(), min_x, max_x)]
+        };
+        let dx = p1.0 - p0.0;
+        if dx < 0.0 {
+            columns.reverse();
+        }
+
+        let mut first: Option<f64> = None;
+        for (column, local_min_x, local_max_x) in columns {
+            // the disc can't reach this column before the move gets within
+            // clearance of its near side, nor any later column
+            let near_x = if dx < 0.0 { local_max_x + clearance } else { local_min_x - clearance };
+            if dx != 0.0 && first.is_some_and(|t| t < (near_x - p0.0) / dx) {
+                break;
+            }
+            let Some((min_y, max_y)) = capsule_y_range(p0, p1, clearance, local_min_x, local_max_x) else {
+                continue
+            };
+            let states = match column {
+                Some(column) if min_y < max_y => column.data_range_clipped(min_y, max_y),
+                Some(column) => vec![(*column.value(min_y), min_y, max_y)],
+                None => vec![(None, min_y, max_y)],
+            };
+            for (state, local_min_y, local_max_y) in states {
+                if state == Some(FillState::Open) {
+                    continue;
+                }
+                let rect = (local_min_x, local_min_y, local_max_x, local_max_y);
+                let Some(t) = rect_entry(p0, p1, clearance, rect) else { continue };
+                if first.is_none_or(|first| t < first) {
+                    first = Some(t);
+                }
+            }
+        }
+
+        let t = first.unwrap_or(0.0);
+        if t <= 0.0 {
+            return Some(p0);
+        }
+        return Some((p0.0 + (p1.0 - p0.0) * t, p0.1 + (p1.1 - p0.1) * t));
+    }
+}
+
+// First t in [0, 1] where the disc of radius around p0 + (p1 - p0) * t
+// touches the rect. The rect grown by radius is two boxes and four corner
+// circles, so this is the first t in any of them.
+fn rect_entry(
+    p0: (f64, f64), p1: (f64, f64), radius: f64, rect: (f64, f64, f64, f64),
+) -> Option<f64> {
+    let (min_x, min_y, max_x, max_y) = rect;
+    let mut r: Option<f64> = None;
+    let mut add = |t: Option<f64>| {
+        let Some(t) = t else { return };
+        if r.is_none_or(|r| t < r) {
+            r = Some(t);
+        }
+    };
+    add(box_entry(p0, p1, (min_x - radius, min_y, max_x + radius, max_y)));
+    add(box_entry(p0, p1, (min_x, min_y - radius, max_x, max_y + radius)));
+    for corner in [(min_x, min_y), (min_x, max_y), (max_x, min_y), (max_x, max_y)] {
+        add(circle_entry(p0, p1, radius, corner));
+    }
+    return r;
+}
+
+// First t in [0, 1] where the segment is inside the box
+fn box_entry(p0: (f64, f64), p1: (f64, f64), rect: (f64, f64, f64, f64)) -> Option<f64> {
+    let (mut t0, mut t1) = (0.0_f64, 1.0_f64);
+    for (p, d, lo, hi) in [
+        (p0.0, p1.0 - p0.0, rect.0, rect.2),
+        (p0.1, p1.1 - p0.1, rect.1, rect.3),
+    ] {
+        if d == 0.0 {
+            if p < lo || hi < p {
+                return None;
+            }
+            continue;
+        }
+        let (a, b) = ((lo - p) / d, (hi - p) / d);
+        t0 = t0.max(a.min(b));
+        t1 = t1.min(a.max(b));
+    }
+    if t1 < t0 {
+        return None;
+    }
+    return Some(t0);
+}
+
+// First t in [0, 1] where the segment is within radius of center
+fn circle_entry(p0: (f64, f64), p1: (f64, f64), radius: f64, center: (f64, f64)) -> Option<f64> {
+    let (dx, dy) = (p1.0 - p0.0, p1.1 - p0.1);
+    let (fx, fy) = (p0.0 - center.0, p0.1 - center.1);
+    let c = fx * fx + fy * fy - radius * radius;
+    if c <= 0.0 {
+        return Some(0.0);
+    }
+    let a = dx * dx + dy * dy;
+    let b = 2.0 * (fx * dx + fy * dy);
+    let discriminant = b * b - 4.0 * a * c;
+    if a == 0.0 || discriminant < 0.0 {
+        return None;
+    }
+    let t = (-b - discriminant.sqrt()) / (2.0 * a);
+    if (0.0..=1.0).contains(&t) {
+        return Some(t);
+    }
+    return None;
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_capsule_y_range() {
+        let range = |min_x, max_x| capsule_y_range((0.0, 0.0), (4.0, 4.0), 1.0, min_x, max_x);
+        assert_eq!(range(-1.0, 5.0), Some((-1.0, 5.0)));
+        assert_eq!(range(-3.0, -2.0), None);
+        assert_eq!(range(-1.0, -1.0), Some((0.0, 0.0)));
+        let (min_y, max_y) = range(2.0, 3.0).unwrap();
+        assert!((min_y - (2.0 - 2.0_f64.sqrt())).abs() < 1e-9);
+        assert!((max_y - (3.0 + 2.0_f64.sqrt())).abs() < 1e-9);
+    }
+
+    #[test]
+    pub fn test_segment_is_clear() {
+        let mut fill_rect = FillRect::from(0.0, 0.0, 10.0, 10.0);
+        fill_rect.fill_rect(4.0, 4.0, 6.0, 6.0);
+        assert_eq!(fill_rect.segment_is_clear((1.0, 2.0), (9.0, 2.0), 1.0, false), true);
+        assert_eq!(fill_rect.segment_is_clear((1.0, 2.0), (9.0, 2.0), 2.5, false), false);
+        assert_eq!(fill_rect.segment_is_clear((1.0, 1.0), (9.0, 9.0), 0.0, false), false);
+        // the clearance pokes out of the top of the region
+        assert_eq!(fill_rect.segment_is_clear((1.0, 8.0), (3.0, 10.0), 0.5, false), false);
+        // passes the corner of the filled square sqrt(2) away
+        assert_eq!(fill_rect.segment_is_clear((2.0, 6.0), (3.0, 7.0), 1.4, false), true);
+        assert_eq!(fill_rect.segment_is_clear((2.0, 6.0), (3.0, 7.0), 1.5, false), false);
+        assert_eq!(fill_rect.segment_is_clear((4.5, 4.5), (5.5, 5.5), 0.4, true), true);
+        assert_eq!(fill_rect.segment_is_clear((4.5, 4.5), (5.5, 5.5), 0.6, true), false);
+        // vertical segment with no clearance
+        assert_eq!(fill_rect.segment_is_clear((5.0, 1.0), (5.0, 3.0), 0.0, false), true);
+        assert_eq!(fill_rect.segment_is_clear((5.0, 1.0), (5.0, 5.0), 0.0, false), false);
+    }
+
+    #[test]
+    pub fn test_segment_is_clear_outside() {
+        let fill_rect = FillRect::from(0.0, 0.0, 10.0, 10.0);
+        assert_eq!(fill_rect.segment_is_clear((5.0, 5.0), (12.0, 5.0), 0.0, false), false);
+        assert_eq!(fill_rect.segment_is_clear((11.0, 5.0), (12.0, 5.0), 0.5, true), true);
+        assert_eq!(fill_rect.segment_is_clear((5.0, 5.0), (5.0, 9.5), 1.0, false), false);
+    }
+
+    #[test]
+    pub fn test_first_blocking_point() {
+        let mut fill_rect = FillRect::from(0.0, 0.0, 10.0, 10.0);
+        fill_rect.fill_rect(6.0, 0.0, 7.0, 10.0);
+        assert_eq!(fill_rect.first_blocking_point((1.0, 5.0), (4.0, 5.0), 1.0), None);
+        let (x, y) = fill_rect.first_blocking_point((1.0, 5.0), (9.0, 5.0), 1.0).unwrap();
+        assert!((x - 5.0).abs() < 1e-9);
+        assert_eq!(y, 5.0);
+        assert_eq!(fill_rect.first_blocking_point((6.5, 5.0), (1.0, 5.0), 1.0), Some((6.5, 5.0)));
+    }
+
+    #[test]
+    pub fn test_first_blocking_point_corner() {
+        let mut fill_rect = FillRect::from(0.0, 0.0, 10.0, 10.0);
+        fill_rect.fill_rect(5.0, 5.0, 7.0, 7.0);
+        // the disc first touches the corner (5, 5) from 2 below it, so
+        // 5 - sqrt(2.5^2 - 2^2) = 3.5 along x
+        assert_eq!(fill_rect.first_blocking_point((3.0, 3.0), (9.0, 3.0), 2.5), Some((3.5, 3.0)));
+        // walking left under the corner (7, 5) from 0.75 below it, so
+        // 7 + sqrt(1.25^2 - 0.75^2) = 8 along x
+        assert_eq!(fill_rect.first_blocking_point((8.5, 4.25), (1.0, 4.25), 1.25), Some((8.0, 4.25)));
+        // diagonal approach: (s, s) is 1 away from the corner (5, 5) when
+        // s = 5 - sqrt(1 / 2)
+        let (x, y) = fill_rect.first_blocking_point((1.5, 1.5), (4.5, 4.5), 1.0).unwrap();
+        let s = 5.0 - 0.5_f64.sqrt();
+        assert!((x - s).abs() < 1e-12);
+        assert!((y - s).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,306 @@
+use super::fill_rect::FillRect;
+
+// Boundary of one 4-connected region. Every ring keeps the region on its
+// left, so the outer ring runs counter clockwise and holes clockwise.
+// Rings start at their lowest, then leftmost, corner.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Polygon {
+    pub outer: Vec<(f64, f64)>,
+    pub holes: Vec<Vec<(f64, f64)>>,
+}
+
+type Segment = ((f64, f64), (f64, f64));
+
+// (min_x, max_x, covered intervals) in order of x
+type Column = (f64, f64, Vec<(f64, f64)>);
+
+// Touching intervals of a sorted list joined together
+fn merge_intervals(intervals: &[(f64, f64)]) -> Vec<(f64, f64)> {
+    let mut r: Vec<(f64, f64)> = Vec::new();
+    for (min, max) in intervals.iter() {
+        match r.last_mut() {
+            Some(last) if *min <= last.1 => last.1 = last.1.max(*max),
+            _ => r.push((*min, *max)),
+        }
+    }
+    return r;
+}
+
+// Parts of a not covered by b, where both are sorted and merged
+fn subtract(a: &[(f64, f64)], b: &[(f64, f64)]) -> Vec<(f64, f64)> {
+    let mut r = Vec::new();
+    let mut j = 0;
+    for (min, max) in a.iter() {
+        let mut cursor = *min;
+        while j < b.len() && b[j].1 <= cursor {
+            j += 1;
+        }
+        let mut k = j;
+        while k < b.len() && b[k].0 < *max {
+            if cursor < b[k].0 {
+                r.push((cursor, b[k].0));
+            }
+            cursor = cursor.max(b[k].1);
+            k += 1;
+        }
+        if cursor < *max {
+            r.push((cursor, *max));
+        }
+    }
+    return r;
+}
+
+// Covered area is on the left of every segment
+fn boundary_segments(columns: &[Column]) -> Vec<Segment> {
+    fn vertical(x: f64, left: &[(f64, f64)], right: &[(f64, f64)], r: &mut Vec<Segment>) {
+        for (min_y, max_y) in subtract(left, right) {
+            r.push(((x, min_y), (x, max_y)));
+        }
+        for (min_y, max_y) in subtract(right, left) {
+            r.push(((x, max_y), (x, min_y)));
+        }
+    }
+
+    let mut r = Vec::new();
+    for i in 0..columns.len() {
+        let (min_x, max_x, intervals) = &columns[i];
+        let previous = if i > 0 && columns[i-1].1 == *min_x { &columns[i-1].2[..] } else { &[] };
+        vertical(*min_x, previous, intervals, &mut r);
+        if i + 1 == columns.len() || columns[i+1].0 != *max_x {
+            vertical(*max_x, intervals, &[], &mut r);
+        }
+        for (min_y, max_y) in intervals.iter() {
+            r.push(((*min_x, *min_y), (*max_x, *min_y)));
+            r.push(((*max_x, *max_y), (*min_x, *max_y)));
+        }
+    }
+    return r;
+}
+
+fn direction(segment: &Segment) -> (f64, f64) {
+    let ((x0, y0), (x1, y1)) = *segment;
+    (x1 - x0, y1 - y0)
+}
+
+// 0 for a left turn, 1 straight on, 2 for a right turn and 3 to go back
+fn turn_rank(from: (f64, f64), to: (f64, f64)) -> usize {
+    let cross = from.0 * to.1 - from.1 * to.0;
+    let dot = from.0 * to.0 + from.1 * to.1;
+    if cross > 0.0 { 0 } else if cross < 0.0 { 2 } else if dot > 0.0 { 1 } else { 3 }
+}
+
+fn signed_area(ring: &[(f64, f64)]) -> f64 {
+    let mut r = 0.0;
+    for i in 0..ring.len() {
+        let (a, b) = (ring[i], ring[(i + 1) % ring.len()]);
+        r += a.0 * b.1 - b.0 * a.1;
+    }
+    return r / 2.0;
+}
+
+fn contains(ring: &[(f64, f64)], point: (f64, f64)) -> bool {
+    let mut inside = false;
+    for i in 0..ring.len() {
+        let (a, b) = (ring[i], ring[(i + 1) % ring.len()]);
+        if (a.1 <= point.1) != (b.1 <= point.1) {
+            let x = a.0 + (point.1 - a.1) * (b.0 - a.0) / (b.1 - a.1);
+            if point.0 < x {
+                inside = !inside;
+            }
+        }
+    }
+    return inside;
+}
+
+// Links the segments into closed rings. Where two rings touch at a corner
+// the leftmost turn is taken, which keeps regions that only share a corner
+// apart.
+fn link(segments: &[Segment]) -> Vec<Vec<(f64, f64)>> {
+    let cmp = |a: &(f64, f64), b: &(f64, f64)| a.partial_cmp(b).unwrap();
+    let mut by_start: Vec<usize> = (0..segments.len()).collect();
+    by_start.sort_by(|a, b| cmp(&segments[*a].0, &segments[*b].0));
+    let mut used = vec![false; segments.len()];
+
+    let mut r = Vec::new();
+    for first in 0..segments.len() {
+        if used[first] {
+            continue;
+        }
+        used[first] = true;
+        let start = segments[first].0;
+        let mut ring = vec![start];
+        let mut current = first;
+        loop {
+            let end = segments[current].1;
+            let from = direction(&segments[current]);
+            let lower = by_start.partition_point(|i| cmp(&segments[*i].0, &end).is_lt());
+            let mut candidates: Vec<usize> = by_start[lower..].iter()
+                .take_while(|i| segments[**i].0 == end)
+                .filter(|i| !used[**i])
+                .copied()
+                .collect();
+            if end == start {
+                candidates.push(first);
+            }
+            let Some(next) = candidates.into_iter()
+                .min_by_key(|i| turn_rank(from, direction(&segments[*i])))
+            else { break };
+            if next == first {
+                break;
+            }
+            used[next] = true;
+            ring.push(end);
+            current = next;
+        }
+
+        // drop corners between collinear segments
+        let mut i = 0;
+        while ring.len() > 2 && i < ring.len() {
+            let n = ring.len();
+            let (a, b, c) = (ring[(i + n - 1) % n], ring[i], ring[(i + 1) % n]);
+            if (a.0 == b.0 && b.0 == c.0) || (a.1 == b.1 && b.1 == c.1) {
+                ring.remove(i);
+            } else {
+                i += 1;
+            }
+        }
+
+        let lowest = (0..ring.len())
+            .min_by(|a, b| (ring[*a].1, ring[*a].0).partial_cmp(&(ring[*b].1, ring[*b].0)).unwrap())
+            .unwrap_or(0);
+        ring.rotate_left(lowest);
+        r.push(ring);
+    }
+    return r;
+}
+
+// Polygons around the covered intervals of the columns
+fn contours(mut columns: Vec<Column>) -> Vec<Polygon> {
+    for column in columns.iter_mut() {
+        column.2 = merge_intervals(&column.2);
+    }
+
+    let mut r: Vec<Polygon> = Vec::new();
+    let mut holes = Vec::new();
+    for ring in link(&boundary_segments(&columns)) {
+        if signed_area(&ring) > 0.0 {
+            r.push(Polygon {
+                outer: ring,
+                holes: Vec::new(),
+            });
+        } else {
+            holes.push(ring);
+        }
+    }
+
+    for hole in holes {
+        // the middle of an edge is strictly inside the polygon around the hole
+        let point = ((hole[0].0 + hole[1].0) / 2.0, (hole[0].1 + hole[1].1) / 2.0);
+        let around = (0..r.len())
+            .filter(|i| contains(&r[*i].outer, point))
+            .min_by(|a, b| signed_area(&r[*a].outer).partial_cmp(&signed_area(&r[*b].outer)).unwrap());
+        if let Some(i) = around {
+            r[i].holes.push(hole);
+        }
+    }
+
+    return r;
+}
+
+impl FillRect {
+    // Outlines of the open area as polygons with holes, traced along the
+    // cell edges with the edges between two open cells left out.
+    pub fn open_contours(&self) -> Vec<Polygon> {
+        let mut columns: Vec<Column> = Vec::new();
+        for (min_x, min_y, max_x, max_y) in self.get_open_rects() {
+            match columns.last_mut() {
+                Some(column) if column.0 == min_x => column.2.push((min_y, max_y)),
+                _ => columns.push((min_x, max_x, vec![(min_y, max_y)])),
+            }
+        }
+        contours(columns)
+    }
+
+    // Outlines of the work region itself, filled or open
+    pub fn region_contours(&self) -> Vec<Polygon> {
+        let columns = self.columns().into_iter()
+            .map(|(min_x, max_x, cells)| {
+                (min_x, max_x, cells.into_iter().map(|(_, min_y, max_y)| (min_y, max_y)).collect())
+            })
+            .collect();
+        contours(columns)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_subtract() {
+        assert_eq!(subtract(&[(0.0, 10.0)], &[(2.0, 3.0), (5.0, 12.0)]), vec![(0.0, 2.0), (3.0, 5.0)]);
+        assert_eq!(subtract(&[(0.0, 1.0), (2.0, 3.0)], &[]), vec![(0.0, 1.0), (2.0, 3.0)]);
+        assert_eq!(subtract(&[(0.0, 1.0)], &[(0.0, 1.0)]), vec![]);
+    }
+
+    #[test]
+    pub fn test_open_contours_rect() {
+        let fill_rect = FillRect::from(0.0, 0.0, 10.0, 5.0);
+        assert_eq!(fill_rect.open_contours(), vec![Polygon {
+            outer: vec![(0.0, 0.0), (10.0, 0.0), (10.0, 5.0), (0.0, 5.0)],
+            holes: vec![],
+        }]);
+    }
+
+    #[test]
+    pub fn test_open_contours_hole() {
+        let mut fill_rect = FillRect::from(0.0, 0.0, 10.0, 10.0);
+        fill_rect.fill_rect(2.0, 2.0, 8.0, 8.0);
+        assert_eq!(fill_rect.open_contours(), vec![Polygon {
+            outer: vec![(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)],
+            holes: vec![vec![(2.0, 2.0), (2.0, 8.0), (8.0, 8.0), (8.0, 2.0)]],
+        }]);
+    }
+
+    #[test]
+    pub fn test_open_contours_steps() {
+        let mut fill_rect = FillRect::from(0.0, 0.0, 3.0, 3.0);
+        fill_rect.fill_rect(1.0, 2.0, 3.0, 3.0);
+        fill_rect.fill_rect(2.0, 1.0, 3.0, 2.0);
+        assert_eq!(fill_rect.open_contours(), vec![Polygon {
+            outer: vec![
+                (0.0, 0.0), (3.0, 0.0), (3.0, 1.0), (2.0, 1.0),
+                (2.0, 2.0), (1.0, 2.0), (1.0, 3.0), (0.0, 3.0),
+            ],
+            holes: vec![],
+        }]);
+    }
+
+    #[test]
+    pub fn test_open_contours_corner() {
+        let mut fill_rect = FillRect::from(0.0, 0.0, 2.0, 2.0);
+        fill_rect.fill_rect(0.0, 0.0, 1.0, 1.0);
+        fill_rect.fill_rect(1.0, 1.0, 2.0, 2.0);
+        assert_eq!(fill_rect.open_contours(), vec![
+            Polygon {
+                outer: vec![(0.0, 1.0), (1.0, 1.0), (1.0, 2.0), (0.0, 2.0)],
+                holes: vec![],
+            },
+            Polygon {
+                outer: vec![(1.0, 0.0), (2.0, 0.0), (2.0, 1.0), (1.0, 1.0)],
+                holes: vec![],
+            },
+        ]);
+    }
+
+    #[test]
+    pub fn test_region_contours() {
+        let mut fill_rect = FillRect::from(0.0, 0.0, 4.0, 4.0);
+        fill_rect.fill_rect(1.0, 1.0, 2.0, 2.0);
+        fill_rect.extend_region(4.0, 0.0, 6.0, 2.0);
+        assert_eq!(fill_rect.region_contours(), vec![Polygon {
+            outer: vec![(0.0, 0.0), (6.0, 0.0), (6.0, 2.0), (4.0, 2.0), (4.0, 4.0), (0.0, 4.0)],
+            holes: vec![],
+        }]);
+    }
+}
@@ -0,0 +1,109 @@
+use super::fill_rect::{FillRect, FillState};
+
+impl FillRect {
+    // (filled, open) area of the work region inside the window. Cells outside
+    // of the region count as neither.
+    fn areas_in(&self, min_x: f64, min_y: f64, max_x: f64, max_y: f64) -> (f64, f64) {
+        let (min_x, max_x) = (min_x.min(max_x), min_x.max(max_x));
+        let (min_y, max_y) = (min_y.min(max_y), min_y.max(max_y));
+        let (mut filled, mut open) = (0.0, 0.0);
+        for (column, local_min_x, local_max_x) in self.map.data_range_clipped(min_x, max_x) {
+            let Some(column) = column else { continue };
+            for (state, local_min_y, local_max_y) in column.data_range_clipped(min_y, max_y) {
+                let Some(state) = state else { continue };
+                let area = (local_max_x - local_min_x) * (local_max_y - local_min_y);
+                match state {
+                    FillState::Filled => filled += area,
+                    FillState::Open => open += area,
+                }
+            }
+        }
+        return (filled, open);
+    }
+
+    pub fn total_area_in(&self, min_x: f64, min_y: f64, max_x: f64, max_y: f64) -> f64 {
+        let (filled, open) = self.areas_in(min_x, min_y, max_x, max_y);
+        filled + open
+    }
+
+    pub fn filled_area_in(&self, min_x: f64, min_y: f64, max_x: f64, max_y: f64) -> f64 {
+        self.areas_in(min_x, min_y, max_x, max_y).0
+    }
+
+    pub fn open_area_in(&self, min_x: f64, min_y: f64, max_x: f64, max_y: f64) -> f64 {
+        self.areas_in(min_x, min_y, max_x, max_y).1
+    }
+
+    // Filled share of the work region inside the window, 0 when the window
+    // misses the region
+    pub fn coverage_ratio_in(&self, min_x: f64, min_y: f64, max_x: f64, max_y: f64) -> f64 {
+        let (filled, open) = self.areas_in(min_x, min_y, max_x, max_y);
+        if filled + open <= 0.0 {
+            return 0.0;
+        }
+        return filled / (filled + open);
+    }
+
+    pub fn total_area(&self) -> f64 {
+        self.total_area_in(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::INFINITY, f64::INFINITY)
+    }
+
+    pub fn filled_area(&self) -> f64 {
+        self.filled_area_in(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::INFINITY, f64::INFINITY)
+    }
+
+    pub fn open_area(&self) -> f64 {
+        self.open_area_in(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::INFINITY, f64::INFINITY)
+    }
+
+    pub fn coverage_ratio(&self) -> f64 {
+        self.coverage_ratio_in(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::INFINITY, f64::INFINITY)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_areas() {
+        let mut fill_rect = FillRect::from(0.0, 0.0, 10.0, 10.0);
+        assert_eq!(fill_rect.coverage_ratio(), 0.0);
+        fill_rect.fill_rect(0.0, 0.0, 5.0, 4.0);
+        assert_eq!(fill_rect.total_area(), 100.0);
+        assert_eq!(fill_rect.filled_area(), 20.0);
+        assert_eq!(fill_rect.open_area(), 80.0);
+        assert_eq!(fill_rect.coverage_ratio(), 0.2);
+    }
+
+    #[test]
+    pub fn test_areas_in() {
+        let mut fill_rect = FillRect::from(0.0, 0.0, 10.0, 10.0);
+        fill_rect.fill_rect(0.0, 0.0, 5.0, 4.0);
+        assert_eq!(fill_rect.total_area_in(4.0, 2.0, 6.0, 6.0), 8.0);
+        assert_eq!(fill_rect.filled_area_in(4.0, 2.0, 6.0, 6.0), 2.0);
+        assert_eq!(fill_rect.open_area_in(6.0, 6.0, 4.0, 2.0), 6.0);
+        assert_eq!(fill_rect.coverage_ratio_in(4.0, 2.0, 6.0, 6.0), 0.25);
+        // outside of the region
+        assert_eq!(fill_rect.total_area_in(20.0, 0.0, 30.0, 10.0), 0.0);
+        assert_eq!(fill_rect.coverage_ratio_in(20.0, 0.0, 30.0, 10.0), 0.0);
+    }
+
+    #[test]
+    pub fn test_areas_non_rectangular() {
+        // triangle with a square island
+        let mut fill_rect = FillRect::from_polygons_with_holes(
+            &[vec![(0.0, 0.0), (10.0, 0.0), (0.0, 10.0)]],
+            &[vec![(1.0, 1.0), (3.0, 1.0), (3.0, 3.0), (1.0, 3.0)]],
+            0.01,
+        );
+        // strips only cover area inside the triangle
+        assert!(fill_rect.total_area() <= 50.0);
+        assert!(fill_rect.total_area() > 49.5);
+        assert_eq!(fill_rect.filled_area(), 4.0);
+        // cells added outside of the region by extending it count once added
+        fill_rect.extend_region(10.0, 0.0, 12.0, 1.0);
+        assert!(fill_rect.total_area() > 51.5);
+        assert!((fill_rect.open_area() + fill_rect.filled_area() - fill_rect.total_area()).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,241 @@
+use super::fill_rect::{FillRect, FillState};
+use super::persistent_range_map::PersistentRangeMap;
+
+// FillRect one dimension higher: a map over x of FillRects over (y, z).
+#[derive(Debug, Clone, PartialEq)]
+pub struct FillBox {
+    map: PersistentRangeMap<f64, Option<FillRect>>,
+}
+
+impl From<(f64, f64, f64, f64, f64, f64)> for FillBox {
+    fn from(params: (f64, f64, f64, f64, f64, f64)) -> Self {
+        FillBox::from(params.0, params.1, params.2, params.3, params.4, params.5)
+    }
+}
+
+impl FillBox {
+    pub fn from(
+        min_x: f64, min_y: f64, min_z: f64,
+        max_x: f64, max_y: f64, max_z: f64,
+    ) -> Self {
+        let mut range_map = PersistentRangeMap::from(None);
+        range_map.set(Some(FillRect::from(min_y, min_z, max_y, max_z)), min_x, max_x);
+        Self {
+            map: range_map
+        }
+    }
+
+    pub fn is_fill_point(&self, x: f64, y: f64, z: f64) -> bool {
+        if let Some(slice) = self.map.value(x) {
+            return slice.value_at(y, z) != Some(&FillState::Open);
+        }
+        return true;
+    }
+
+    pub fn is_fill(
+        &self,
+        min_x: f64, min_y: f64, min_z: f64,
+        max_x: f64, max_y: f64, max_z: f64,
+    ) -> bool {
+        for slice in self.map.values(min_x, max_x) {
+            let Some(slice) = slice else { return false; };
+            if !slice.is_fill(min_y, min_z, max_y, max_z) {
+                return false;
+            }
+        }
+        return true;
+    }
+
+    // min, max and padding are (x, y, z)
+    pub fn is_fill_padding(
+        &self,
+        min: (f64, f64, f64),
+        max: (f64, f64, f64),
+        padding: (f64, f64, f64),
+    ) -> bool {
+        let ((min_x, min_y, min_z), (max_x, max_y, max_z)) = (min, max);
+        let (x_padding, y_padding, z_padding) = padding;
+        let (min_x, max_x) = (min_x.min(max_x), min_x.max(max_x));
+        let (min_y, max_y) = (min_y.min(max_y), min_y.max(max_y));
+        let (min_z, max_z) = (min_z.min(max_z), min_z.max(max_z));
+        return self.is_fill(
+            min_x - x_padding, min_y - y_padding, min_z - z_padding,
+            max_x + x_padding, max_y + y_padding, max_z + z_padding,
+        );
+    }
+
+    pub fn biggest_unfilled_volume_block(
+        &self,
+        min_x: f64, min_y: f64, min_z: f64,
+        max_x: f64, max_y: f64, max_z: f64,
+    ) -> f64 {
+        let mut biggest_volume = 0.0;
+        for (slice, local_min_x, local_max_x) in self.map.data_range_address(min_x, max_x) {
+            let area = match slice {
+                Some(slice) => slice.biggest_unfilled_area_block(min_y, min_z, max_y, max_z),
+                None => (max_y - min_y) * (max_z - min_z),
+            };
+            let volume = (local_max_x - local_min_x) * area;
+            if biggest_volume < volume {
+                biggest_volume = volume;
+            }
+        }
+        return biggest_volume;
+    }
+
+    pub fn biggest_unfilled_volume_block_padding(
+        &self,
+        min: (f64, f64, f64),
+        max: (f64, f64, f64),
+        padding: (f64, f64, f64),
+    ) -> f64 {
+        let ((min_x, min_y, min_z), (max_x, max_y, max_z)) = (min, max);
+        let (x_padding, y_padding, z_padding) = padding;
+        let (min_x, max_x) = (min_x.min(max_x), min_x.max(max_x));
+        let (min_y, max_y) = (min_y.min(max_y), min_y.max(max_y));
+        let (min_z, max_z) = (min_z.min(max_z), min_z.max(max_z));
+        return self.biggest_unfilled_volume_block(
+            min_x - x_padding, min_y - y_padding, min_z - z_padding,
+            max_x + x_padding, max_y + y_padding, max_z + z_padding,
+        );
+    }
+
+    // size is (width, height, depth)
+    pub fn contains_unfilled_box_width_height_depth(
+        &self,
+        min: (f64, f64, f64),
+        max: (f64, f64, f64),
+        size: (f64, f64, f64),
+    ) -> bool {
+        let ((min_x, min_y, min_z), (max_x, max_y, max_z)) = (min, max);
+        let (width, height, depth) = size;
+        for (slice, local_min_x, local_max_x) in self.map.data_range_address(min_x, max_x) {
+            if (local_max_x - local_min_x) <= width {
+                continue;
+            }
+            let Some(slice) = slice else {
+                if (max_y - min_y) > height && (max_z - min_z) > depth {
+                    return true;
+                }
+                continue;
+            };
+            if slice.contains_unfilled_rect_width_height(min_y, min_z, max_y, max_z, height, depth) {
+                return true;
+            }
+        }
+        return false;
+    }
+
+    pub fn contains_unfilled_box_width_height_depth_padding(
+        &self,
+        min: (f64, f64, f64),
+        max: (f64, f64, f64),
+        size: (f64, f64, f64),
+        padding: (f64, f64, f64),
+    ) -> bool {
+        let ((min_x, min_y, min_z), (max_x, max_y, max_z)) = (min, max);
+        let (x_padding, y_padding, z_padding) = padding;
+        let (min_x, max_x) = (min_x.min(max_x), min_x.max(max_x));
+        let (min_y, max_y) = (min_y.min(max_y), min_y.max(max_y));
+        let (min_z, max_z) = (min_z.min(max_z), min_z.max(max_z));
+        return self.contains_unfilled_box_width_height_depth(
+            (min_x - x_padding, min_y - y_padding, min_z - z_padding),
+            (max_x + x_padding, max_y + y_padding, max_z + z_padding),
+            size,
+        );
+    }
+
+    pub fn fill_box(
+        &mut self,
+        min_x: f64, min_y: f64, min_z: f64,
+        max_x: f64, max_y: f64, max_z: f64,
+    ) {
+        if max_x < min_x {
+            return self.fill_box(max_x, min_y, min_z, min_x, max_y, max_z);
+        }
+
+        for (slice, local_min_x, local_max_x) in self.map.data_range(min_x, max_x) {
+            let Some(mut slice) = slice else {
+                continue
+            };
+            slice.fill_rect(min_y, min_z, max_y, max_z);
+            self.map.set(
+                Some(slice),
+                local_min_x.max(min_x),
+                local_max_x.min(max_x),
+            );
+        }
+    }
+
+    // (min_x, min_y, min_z, max_x, max_y, max_z) of every open box
+    pub fn get_open_boxes(&self) -> Vec<(f64, f64, f64, f64, f64, f64)> {
+        let mut r = Vec::new();
+
+        for (slice, min_x, max_x) in self.map.data() {
+            let Some(slice) = slice else { continue };
+            if max_x - min_x <= 0.0 { continue; }
+
+            for (min_y, min_z, max_y, max_z) in slice.get_open_rects() {
+                r.push((min_x, min_y, min_z, max_x, max_y, max_z));
+            }
+        }
+
+        return r;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn basic() {
+        let fill_box = FillBox::from(0.0, 0.0, 0.0, 10.0, 10.0, 10.0);
+        assert_eq!(fill_box.is_fill(0.0, 0.0, 0.0, 10.0, 10.0, 10.0), false);
+        assert_eq!(fill_box.get_open_boxes(), vec![(0.0, 0.0, 0.0, 10.0, 10.0, 10.0)]);
+    }
+
+    #[test]
+    pub fn fill_box() {
+        let mut fill_box = FillBox::from(0.0, 0.0, 0.0, 10.0, 10.0, 10.0);
+        fill_box.fill_box(0.0, 0.0, 0.0, 5.0, 10.0, 10.0);
+        fill_box.fill_box(5.0, 0.0, 0.0, 10.0, 10.0, 4.0);
+        assert_eq!(fill_box.is_fill(0.0, 0.0, 0.0, 5.0, 10.0, 10.0), true);
+        assert_eq!(fill_box.is_fill(0.0, 0.0, 0.0, 10.0, 10.0, 4.0), true);
+        assert_eq!(fill_box.is_fill(0.0, 0.0, 0.0, 10.0, 10.0, 5.0), false);
+        assert_eq!(fill_box.is_fill_point(7.0, 5.0, 2.0), true);
+        assert_eq!(fill_box.is_fill_point(7.0, 5.0, 6.0), false);
+        assert_eq!(fill_box.is_fill_point(17.0, 5.0, 6.0), true);
+        assert_eq!(fill_box.get_open_boxes(), vec![(5.0, 0.0, 4.0, 10.0, 10.0, 10.0)]);
+    }
+
+    #[test]
+    pub fn padding() {
+        let mut fill_box = FillBox::from(0.0, 0.0, 0.0, 10.0, 10.0, 10.0);
+        fill_box.fill_box(2.0, 2.0, 2.0, 8.0, 8.0, 8.0);
+        assert_eq!(fill_box.is_fill_padding((4.0, 4.0, 4.0), (6.0, 6.0, 6.0), (2.0, 2.0, 2.0)), true);
+        assert_eq!(fill_box.is_fill_padding((6.0, 6.0, 6.0), (4.0, 4.0, 4.0), (3.0, 2.0, 2.0)), false);
+    }
+
+    #[test]
+    pub fn biggest_unfilled_volume_block() {
+        let mut fill_box = FillBox::from(0.0, 0.0, 0.0, 10.0, 10.0, 10.0);
+        fill_box.fill_box(0.0, 0.0, 0.0, 10.0, 10.0, 6.0);
+        assert_eq!(fill_box.biggest_unfilled_volume_block(0.0, 0.0, 0.0, 10.0, 10.0, 10.0), 400.0);
+        assert_eq!(fill_box.biggest_unfilled_volume_block_padding((5.0, 5.0, 5.0), (5.0, 5.0, 5.0), (1.0, 1.0, 1.0)), 0.0);
+        assert_eq!(
+            fill_box.contains_unfilled_box_width_height_depth((0.0, 0.0, 0.0), (10.0, 10.0, 10.0), (9.0, 9.0, 3.0)),
+            true,
+        );
+        assert_eq!(
+            fill_box.contains_unfilled_box_width_height_depth((0.0, 0.0, 0.0), (10.0, 10.0, 10.0), (9.0, 9.0, 4.0)),
+            false,
+        );
+        assert_eq!(
+            fill_box.contains_unfilled_box_width_height_depth_padding(
+                (5.0, 5.0, 8.0), (5.0, 5.0, 8.0), (1.0, 1.0, 1.0), (1.0, 1.0, 3.0),
+            ),
+            true,
+        );
+    }
+}
@@ -0,0 +1,188 @@
+use super::persistent_range_map::PersistentRangeMap;
+
+// (min_x, max_x, cells) of one column of the region, where cells are
+// (value, min_y, max_y) of its strips inside the region
+pub(crate) type Column<V> = (f64, f64, Vec<(V, f64, f64)>);
+
+// Columns of strips over a 2D region where every cell holds a value. Cells
+// outside of the region hold None. Clones are O(1) and share every column
+// that neither copy has written to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FillGrid<V: PartialEq + Clone> {
+    pub(crate) map: PersistentRangeMap<
+        f64,
+        Option<
+            PersistentRangeMap<
+                f64, Option<V>
+            >
+        >
+    >
+}
+
+impl<V: PartialEq + Clone> Default for FillGrid<V> {
+    fn default() -> Self {
+        Self {
+            map: PersistentRangeMap::from(None),
+        }
+    }
+}
+
+impl<V: PartialEq + Clone> FillGrid<V> {
+    // Grid without any region
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn from_rect(value: V, min_x: f64, min_y: f64, max_x: f64, max_y: f64) -> Self {
+        let mut grid = FillGrid::new();
+        grid.set_rect(value, min_x, min_y, max_x, max_y);
+        return grid;
+    }
+
+    // Sets every cell of the rect, adding it to the region
+    pub fn set_rect(&mut self, value: V, min_x: f64, min_y: f64, max_x: f64, max_y: f64) {
+        if max_x < min_x {
+            return self.set_rect(value, max_x, min_y, min_x, max_y);
+        }
+        if max_y < min_y {
+            return self.set_rect(value, min_x, max_y, max_x, min_y);
+        }
+
+        self.map.update(min_x, max_x, |vertical_ranges| {
+            let mut vertical_ranges = vertical_ranges.clone()
+                .unwrap_or_else(|| PersistentRangeMap::from(None));
+            vertical_ranges.set(Some(value.clone()), min_y, max_y);
+            Some(vertical_ranges)
+        });
+    }
+
+    // Replaces the value of every cell of the rect inside the region. f is
+    // called once per piece of a cell the rect covers.
+    pub fn update_rect<F: FnMut(&V) -> V>(
+        &mut self,
+        min_x: f64, min_y: f64,
+        max_x: f64, max_y: f64,
+        mut f: F,
+    ) {
+        if max_x < min_x {
+            return self.update_rect(max_x, min_y, min_x, max_y, f);
+        }
+        if max_y < min_y {
+            return self.update_rect(min_x, max_y, max_x, min_y, f);
+        }
+
+        self.map.update(min_x, max_x, |vertical_ranges| {
+            let mut vertical_ranges = vertical_ranges.clone()?;
+            vertical_ranges.update(min_y, max_y, |value| value.as_ref().map(&mut f));
+            Some(vertical_ranges)
+        });
+    }
+
+    // None outside of the region
+    pub fn value_at(&self, x: f64, y: f64) -> Option<&V> {
+        self.map.value(x).as_ref()?.value(y).as_ref()
+    }
+
+    // (value, min_x, min_y, max_x, max_y) of every cell inside the region,
+    // column by column from the left and bottom to top within a column
+    pub fn cells(&self) -> Vec<(V, f64, f64, f64, f64)> {
+        let mut r = Vec::new();
+        for (min_x, max_x, cells) in self.columns() {
+            for (value, min_y, max_y) in cells {
+                r.push((value, min_x, min_y, max_x, max_y));
+            }
+        }
+        return r;
+    }
+
+    // Every column of the region from the left
+    pub(crate) fn columns(&self) -> Vec<Column<V>> {
+        let mut r = Vec::new();
+        for (column, min_x, max_x) in self.map.data_range_address(f64::NEG_INFINITY, f64::INFINITY) {
+            let Some(column) = column else { continue };
+            let mut cells = Vec::new();
+            for (value, min_y, max_y) in column.data_range_address(f64::NEG_INFINITY, f64::INFINITY) {
+                let Some(value) = value else { continue };
+                cells.push((value.clone(), min_y, max_y));
+            }
+            r.push((min_x, max_x, cells));
+        }
+        return r;
+    }
+
+    // Inverse of columns(). Columns must be sorted and not overlap.
+    pub(crate) fn from_columns(columns: Vec<Column<V>>) -> Self {
+        let mut range_map = PersistentRangeMap::from(None);
+        for (min_x, max_x, cells) in columns {
+            let mut vertical_map = PersistentRangeMap::from(None);
+            for (value, min_y, max_y) in cells {
+                vertical_map.set(Some(value), min_y, max_y);
+            }
+            if !vertical_map.get_ranges().is_empty() {
+                range_map.set(Some(vertical_map), min_x, max_x);
+            }
+        }
+        Self {
+            map: range_map
+        }
+    }
+
+    // (min_x, min_y, max_x, max_y) around every column of the region
+    pub fn bounds(&self) -> Option<(f64, f64, f64, f64)> {
+        let mut r: Option<(f64, f64, f64, f64)> = None;
+        for (column, min_x, max_x) in self.map.data_range_address(f64::NEG_INFINITY, f64::INFINITY) {
+            let Some(column) = column else { continue };
+            let ys = column.get_ranges();
+            if ys.is_empty() {
+                continue;
+            }
+            let (min_y, max_y) = (ys[0], ys[ys.len()-1]);
+            r = Some(match r {
+                Some(b) => (b.0.min(min_x), b.1.min(min_y), b.2.max(max_x), b.3.max(max_y)),
+                None => (min_x, min_y, max_x, max_y),
+            });
+        }
+        return r;
+    }
+
+    pub fn get_ys(&self, x: f64) -> Option<&Vec<f64>> {
+        if let Some(range) = self.map.value(x) {
+            Some(range.get_ranges())
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_set_rect() {
+        let mut grid = FillGrid::from_rect(0, 0.0, 0.0, 10.0, 10.0);
+        grid.set_rect(1, 5.0, 5.0, 15.0, 15.0);
+        assert_eq!(grid.value_at(1.0, 1.0), Some(&0));
+        assert_eq!(grid.value_at(6.0, 6.0), Some(&1));
+        assert_eq!(grid.value_at(12.0, 12.0), Some(&1));
+        assert_eq!(grid.value_at(12.0, 1.0), None);
+        assert_eq!(grid.bounds(), Some((0.0, 0.0, 15.0, 15.0)));
+        assert_eq!(grid.cells(), vec![
+            (0, 0.0, 0.0, 5.0, 10.0),
+            (0, 5.0, 0.0, 10.0, 5.0),
+            (1, 5.0, 5.0, 10.0, 15.0),
+            (1, 10.0, 5.0, 15.0, 15.0),
+        ]);
+    }
+
+    #[test]
+    pub fn test_update_rect() {
+        let mut grid = FillGrid::from_rect(1, 0.0, 0.0, 10.0, 10.0);
+        grid.update_rect(5.0, 5.0, 20.0, 20.0, |pass| pass + 1);
+        assert_eq!(grid.value_at(6.0, 6.0), Some(&2));
+        assert_eq!(grid.value_at(4.0, 6.0), Some(&1));
+        // outside of the region stays outside
+        assert_eq!(grid.value_at(12.0, 12.0), None);
+        assert_eq!(grid.bounds(), Some((0.0, 0.0, 10.0, 10.0)));
+    }
+}
@@ -0,0 +1,201 @@
+use super::fill_rect::FillState;
+use super::persistent_range_map::PersistentRangeMap;
+
+// One dimension of a FillN. Every level but the last maps its coordinate to
+// the level of the next dimension, None outside of the region.
+#[derive(Debug, Clone, PartialEq)]
+enum Level {
+    Cells(PersistentRangeMap<f64, Option<FillState>>),
+    Slices(PersistentRangeMap<f64, Option<Level>>),
+}
+
+impl Level {
+    fn from(min: &[f64], max: &[f64]) -> Self {
+        if min.len() == 1 {
+            let mut cells = PersistentRangeMap::from(None);
+            cells.set(Some(FillState::Open), min[0], max[0]);
+            return Level::Cells(cells);
+        }
+        let mut slices = PersistentRangeMap::from(None);
+        slices.set(Some(Level::from(&min[1..], &max[1..])), min[0], max[0]);
+        return Level::Slices(slices);
+    }
+
+    fn fill(&mut self, min: &[f64], max: &[f64]) {
+        match self {
+            Level::Cells(cells) => cells.set(Some(FillState::Filled), min[0], max[0]),
+            Level::Slices(slices) => slices.update(min[0], max[0], |slice| {
+                let mut slice = slice.clone()?;
+                slice.fill(&min[1..], &max[1..]);
+                Some(slice)
+            }),
+        }
+    }
+
+    fn is_fill(&self, min: &[f64], max: &[f64]) -> bool {
+        match self {
+            Level::Cells(cells) => cells.values(min[0], max[0]).into_iter()
+                .all(|value| *value != Some(FillState::Open)),
+            Level::Slices(slices) => slices.values(min[0], max[0]).into_iter()
+                .all(|slice| slice.as_ref().is_some_and(|slice| slice.is_fill(&min[1..], &max[1..]))),
+        }
+    }
+
+    fn is_fill_point(&self, point: &[f64]) -> bool {
+        match self {
+            Level::Cells(cells) => *cells.value(point[0]) != Some(FillState::Open),
+            Level::Slices(slices) => match slices.value(point[0]) {
+                Some(slice) => slice.is_fill_point(&point[1..]),
+                None => true,
+            },
+        }
+    }
+
+    // (min, max) corners, each holding this level's coordinate first
+    fn open_boxes(&self) -> Vec<(Vec<f64>, Vec<f64>)> {
+        let mut r = Vec::new();
+        match self {
+            Level::Cells(cells) => {
+                for (value, min, max) in cells.data() {
+                    if value == Some(FillState::Open) && min < max {
+                        r.push((vec![min], vec![max]));
+                    }
+                }
+            },
+            Level::Slices(slices) => {
+                for (slice, min, max) in slices.data() {
+                    let Some(slice) = slice else { continue };
+                    if max <= min { continue; }
+                    for (mut local_min, mut local_max) in slice.open_boxes() {
+                        local_min.insert(0, min);
+                        local_max.insert(0, max);
+                        r.push((local_min, local_max));
+                    }
+                }
+            },
+        }
+        return r;
+    }
+
+    fn measure(&self) -> f64 {
+        let mut r = 0.0;
+        match self {
+            Level::Cells(cells) => {
+                for (value, min, max) in cells.data() {
+                    if value == Some(FillState::Open) {
+                        r += max - min;
+                    }
+                }
+            },
+            Level::Slices(slices) => {
+                for (slice, min, max) in slices.data() {
+                    let Some(slice) = slice else { continue };
+                    r += (max - min) * slice.measure();
+                }
+            },
+        }
+        return r;
+    }
+}
+
+// FillRect for any number of dimensions D > 0, nesting one RangeMap per
+// dimension. Corners are [x, y, z, ...]. FillN<2> behaves like FillRect.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FillN<const D: usize> {
+    root: Level,
+}
+
+impl<const D: usize> FillN<D> {
+    // FillN<0> fails to compile once it is constructed
+    const AT_LEAST_ONE_DIMENSION: () = assert!(D > 0, "FillN needs at least one dimension");
+
+    pub fn from(min: [f64; D], max: [f64; D]) -> Self {
+        let () = Self::AT_LEAST_ONE_DIMENSION;
+        Self {
+            root: Level::from(&min, &max),
+        }
+    }
+
+    pub fn fill(&mut self, min: [f64; D], max: [f64; D]) {
+        let (min, max) = sorted(min, max);
+        self.root.fill(&min, &max);
+    }
+
+    pub fn is_fill(&self, min: [f64; D], max: [f64; D]) -> bool {
+        let (min, max) = sorted(min, max);
+        self.root.is_fill(&min, &max)
+    }
+
+    pub fn is_fill_point(&self, point: [f64; D]) -> bool {
+        self.root.is_fill_point(&point)
+    }
+
+    pub fn open_boxes(&self) -> Vec<([f64; D], [f64; D])> {
+        self.root.open_boxes().into_iter()
+            .map(|(min, max)| (min.try_into().unwrap(), max.try_into().unwrap()))
+            .collect()
+    }
+
+    // Total length, area, volume... of the open region
+    pub fn measure(&self) -> f64 {
+        self.root.measure()
+    }
+}
+
+fn sorted<const D: usize>(mut min: [f64; D], mut max: [f64; D]) -> ([f64; D], [f64; D]) {
+    for i in 0..D {
+        if max[i] < min[i] {
+            std::mem::swap(&mut min[i], &mut max[i]);
+        }
+    }
+    return (min, max);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use super::super::fill_rect::FillRect;
+
+    #[test]
+    pub fn test_one_dimension() {
+        let mut fill = FillN::from([0.0], [10.0]);
+        fill.fill([2.0], [4.0]);
+        assert_eq!(fill.open_boxes(), vec![([0.0], [2.0]), ([4.0], [10.0])]);
+        assert_eq!(fill.measure(), 8.0);
+        assert_eq!(fill.is_fill([2.0], [4.0]), true);
+        assert_eq!(fill.is_fill_point([5.0]), false);
+    }
+
+    #[test]
+    pub fn test_matches_fill_rect() {
+        let mut fill = FillN::from([10.0, 100.0], [20.0, 110.0]);
+        let mut fill_rect = FillRect::from(10.0, 100.0, 20.0, 110.0);
+        for (min, max) in [([10.0, 100.0], [15.0, 110.0]), ([18.0, 105.0], [15.0, 100.0])] {
+            fill.fill(min, max);
+            fill_rect.fill_rect(min[0], min[1], max[0], max[1]);
+        }
+        let open_rects: Vec<([f64; 2], [f64; 2])> = fill_rect.get_open_rects().into_iter()
+            .map(|(min_x, min_y, max_x, max_y)| ([min_x, min_y], [max_x, max_y]))
+            .collect();
+        assert_eq!(fill.open_boxes(), open_rects);
+        assert_eq!(fill.is_fill([10.0, 100.0], [15.0, 110.0]), true);
+        assert_eq!(fill.is_fill([10.0, 100.0], [20.0, 110.0]), false);
+        assert_eq!(fill.measure(), 5.0 * 10.0 - 3.0 * 5.0);
+    }
+
+    #[test]
+    pub fn test_four_dimensions() {
+        let mut fill = FillN::from([0.0, 0.0, 0.0, 0.0], [2.0, 2.0, 2.0, 10.0]);
+        assert_eq!(fill.measure(), 80.0);
+        // reserve the whole space from t = 0 to 4, then one corner until 6
+        fill.fill([0.0, 0.0, 0.0, 0.0], [2.0, 2.0, 2.0, 4.0]);
+        fill.fill([0.0, 0.0, 0.0, 4.0], [1.0, 1.0, 1.0, 6.0]);
+        assert_eq!(fill.measure(), 80.0 - 32.0 - 2.0);
+        assert_eq!(fill.is_fill_point([0.5, 0.5, 0.5, 5.0]), true);
+        assert_eq!(fill.is_fill_point([1.5, 0.5, 0.5, 5.0]), false);
+        assert_eq!(fill.is_fill_point([1.5, 0.5, 0.5, 11.0]), true);
+        assert_eq!(fill.is_fill([0.0, 0.0, 0.0, 0.0], [1.0, 1.0, 1.0, 6.0]), true);
+        assert_eq!(fill.is_fill([0.0, 0.0, 0.0, 0.0], [1.0, 1.0, 1.0, 7.0]), false);
+        assert_eq!(fill.open_boxes()[0], ([0.0, 0.0, 0.0, 6.0], [1.0, 1.0, 1.0, 10.0]));
+    }
+}
@@ -1,17 +1,52 @@
-use super::range_map;
-
-#[derive(Debug, Clone, PartialEq)]
-pub struct FillRect {
-    map: range_map::RangeMap<
-        f64,
-        Option<
-            range_map::RangeMap<
-                f64, Option<bool>
-            >
-        >
-    >
+use super::fill_grid::FillGrid;
+use super::journal::Journaled;
+use super::persistent_range_map::PersistentRangeMap;
+use super::polygon;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FillState {
+    Open,
+    Filled,
+}
+
+pub type FillRect = FillGrid<FillState>;
+
+// The window cut on every column and strip boundary inside it.
+// open[i][j] is whether the cell xs[i]..xs[i+1] by ys[j]..ys[j+1] is open.
+pub(crate) struct OpenGrid {
+    pub xs: Vec<f64>,
+    pub ys: Vec<f64>,
+    pub open: Vec<Vec<bool>>,
+}
+
+impl OpenGrid {
+    // blocked[i][j] counts the cells in columns ..i and strips ..j that are not open
+    fn blocked_prefix(&self) -> Vec<Vec<usize>> {
+        let rows = self.ys.len().saturating_sub(1);
+        let mut blocked = vec![vec![0; rows + 1]; self.open.len() + 1];
+        for i in 0..self.open.len() {
+            for j in 0..rows {
+                blocked[i+1][j+1] = blocked[i][j+1] + blocked[i+1][j] - blocked[i][j]
+                    + if self.open[i][j] { 0 } else { 1 };
+            }
+        }
+        return blocked;
+    }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlacementStrategy {
+    // Lowest y, then lowest x
+    BottomLeft,
+    // Smallest open rectangle grown right then up from the placement,
+    // ties broken bottom left
+    BestAreaFit,
+}
+
+// Sizes within this of the open space still fit, so that sums that round up
+// like 0.1 + 0.2 don't miss an exact fit
+const PLACEMENT_EPSILON: f64 = 1e-9;
+
 fn max<T: PartialOrd>(a: T, b: T) -> T {
     if a < b { b } else { a }
 }
@@ -25,14 +60,23 @@ impl From<(f64, f64, f64, f64)> for FillRect {
     }
 }
 
+// Snapshots are O(1), so a FillRect reverts by swapping in the old one.
+impl Journaled for FillRect {
+    type Patch = FillRect;
+
+    fn apply_patch(&mut self, patch: Self::Patch) -> Self::Patch {
+        std::mem::replace(self, patch)
+    }
+}
+
 impl FillRect {
     pub fn from(
         min_x: f64, min_y: f64, max_x: f64, max_y: f64
     ) -> Self {
-        let mut range_map = range_map::RangeMap::from(None);
+        let mut range_map = PersistentRangeMap::from(None);
         {
-            let mut vertical_map = range_map::RangeMap::from(None);
-            vertical_map.set(Some(false), min_y, max_y);
+            let mut vertical_map = PersistentRangeMap::from(None);
+            vertical_map.set(Some(FillState::Open), min_y, max_y);
             range_map.set(Some(vertical_map), min_x, max_x);
         }
         Self {
@@ -40,10 +84,52 @@ impl FillRect {
         }
     }
 
+    // Work region inside the polygon. Strips under slanted edges are at most
+    // tolerance wide and only cover area inside the polygon.
+    pub fn from_polygon(points: &[(f64, f64)], tolerance: f64) -> Self {
+        FillRect::from_polygons_with_holes(&[points.to_vec()], &[], tolerance)
+    }
+
+    // Work region inside the polygons, with the holes (islands) marked as
+    // filled. Islands are grown to the strips rather than shrunk.
+    pub fn from_polygons_with_holes(
+        polygons: &[Vec<(f64, f64)>],
+        holes: &[Vec<(f64, f64)>],
+        tolerance: f64,
+    ) -> Self {
+        let outer_edges: Vec<polygon::Edge> = polygons.iter()
+            .flat_map(|ring| polygon::ring_edges(ring))
+            .collect();
+        let hole_edges: Vec<polygon::Edge> = holes.iter()
+            .flat_map(|ring| polygon::ring_edges(ring))
+            .collect();
+        let splits = polygons.iter().chain(holes.iter())
+            .flat_map(|ring| ring.iter().map(|(x, _)| *x))
+            .collect();
+
+        let mut range_map = PersistentRangeMap::from(None);
+        let edges: Vec<polygon::Edge> = outer_edges.iter().chain(hole_edges.iter()).copied().collect();
+        for (min_x, max_x) in polygon::strips(splits, &edges, tolerance) {
+            let mut vertical_map = PersistentRangeMap::from(None);
+            for (min_y, max_y) in polygon::strip_intervals(&outer_edges, min_x, max_x, true) {
+                vertical_map.set(Some(FillState::Open), min_y, max_y);
+            }
+            for (min_y, max_y) in polygon::strip_intervals(&hole_edges, min_x, max_x, false) {
+                vertical_map.update(min_y, max_y, |value| value.map(|_| FillState::Filled));
+            }
+            if !vertical_map.get_ranges().is_empty() {
+                range_map.set(Some(vertical_map), min_x, max_x);
+            }
+        }
+        Self {
+            map: range_map
+        }
+    }
+
     pub fn is_fill_point(&mut self, x: f64, y: f64) -> bool {
         if let Some(y_range) = self.map.value(x) {
-            if let Some(is_fill) = y_range.value(y) {
-                return *is_fill;
+            if let Some(state) = y_range.value(y) {
+                return *state == FillState::Filled;
             }
         }
         return true;
@@ -55,7 +141,7 @@ impl FillRect {
             let Some(maps) = maps else { return false; };
             for value in maps.values(min_y, max_y) {
                 let Some(value) = value else { continue };
-                if *value == false {
+                if *value == FillState::Open {
                     return false;
                 }
             }
@@ -93,7 +179,7 @@ impl FillRect {
             };
             for (value, local_min_y, local_max_y) in maps.data_range_address(min_y, max_y) {
                 let Some(value) = value else { continue };
-                if *value == false {
+                if *value == FillState::Open {
                     let area = (local_max_x - local_min_x) * (local_max_y - local_min_y);
                     if biggest_area < area {
                         biggest_area = area;
@@ -139,7 +225,7 @@ impl FillRect {
             };
             for (value, local_min_y, local_max_y) in maps.data_range_address(min_y, max_y) {
                 let Some(value) = value else { continue };
-                if *value == false {
+                if *value == FillState::Open {
                     if (local_max_x - local_min_x) > width &&
                         (local_max_y - local_min_y) > height
                     {
@@ -187,7 +273,7 @@ impl FillRect {
             let Some(mut vertical_ranges) = vertical_ranges else {
                 continue
             };
-            vertical_ranges.set(Some(true), min_y, max_y);
+            vertical_ranges.set(Some(FillState::Filled), min_y, max_y);
             self.map.set(
                 Some(vertical_ranges),
                 max(local_min_x, min_x),
@@ -196,6 +282,102 @@ impl FillRect {
         }
     }
 
+    // Fills strips inside the circle, no wider than tolerance
+    pub fn fill_circle(&mut self, cx: f64, cy: f64, r: f64, tolerance: f64) {
+        let chord = |x| polygon::circle_chord((cx, cy), r, x);
+        self.fill_convex(chord, cx - r, cx + r, tolerance);
+    }
+
+    // Fills strips inside the polygon, no wider than tolerance under slanted edges
+    pub fn fill_polygon(&mut self, points: &[(f64, f64)], tolerance: f64) {
+        let edges = polygon::ring_edges(points);
+        let mut splits: Vec<f64> = points.iter().map(|(x, _)| *x).collect();
+        let (min_x, max_x) = splits.iter()
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(a, b), x| (a.min(*x), b.max(*x)));
+        splits.append(&mut self.column_splits(min_x, max_x));
+
+        for (local_min_x, local_max_x) in polygon::strips(splits, &edges, tolerance) {
+            for (min_y, max_y) in polygon::strip_intervals(&edges, local_min_x, local_max_x, true) {
+                self.fill_rect(local_min_x, min_y, local_max_x, max_y);
+            }
+        }
+    }
+
+    // Fills strips inside the area swept by a disc of radius r moved from p0
+    // to p1, no wider than a sixteenth of r
+    pub fn fill_capsule(&mut self, p0: (f64, f64), p1: (f64, f64), r: f64) {
+        self.fill_capsule_with_tolerance(p0, p1, r, r / 16.0);
+    }
+
+    // fill_capsule with strips no wider than tolerance
+    pub fn fill_capsule_with_tolerance(&mut self, p0: (f64, f64), p1: (f64, f64), r: f64, tolerance: f64) {
+        let chord = |x| polygon::capsule_chord(p0, p1, r, x);
+        self.fill_convex(chord, p0.0.min(p1.0) - r, p0.0.max(p1.0) + r, tolerance);
+    }
+
+    fn fill_convex<F: Fn(f64) -> Option<(f64, f64)>>(
+        &mut self, chord: F, min_x: f64, max_x: f64, tolerance: f64,
+    ) {
+        if max_x <= min_x {
+            return;
+        }
+        let splits = self.column_splits(min_x, max_x);
+        for i in 1..splits.len() {
+            for (local_min_x, local_max_x) in polygon::split_strip(splits[i-1], splits[i], tolerance) {
+                let Some((min_y, max_y)) = polygon::convex_inner(&chord, local_min_x, local_max_x) else {
+                    continue
+                };
+                self.fill_rect(local_min_x, min_y, local_max_x, max_y);
+            }
+        }
+    }
+
+    // min_x, every column boundary between, and max_x
+    fn column_splits(&self, min_x: f64, max_x: f64) -> Vec<f64> {
+        let mut splits = vec![min_x];
+        for x in self.map.get_ranges() {
+            if min_x < *x && *x < max_x {
+                splits.push(*x);
+            }
+        }
+        splits.push(max_x);
+        return splits;
+    }
+
+    // Reopens filled area, leaving anything outside of the work region alone
+    pub fn unfill_rect(&mut self, min_x: f64, min_y: f64, max_x: f64, max_y: f64) {
+        if max_x < min_x {
+            return self.unfill_rect(max_x, min_y, min_x, max_y);
+        }
+        if max_y < min_y {
+            return self.unfill_rect(min_x, max_y, max_x, min_y);
+        }
+
+        self.map.update(min_x, max_x, |vertical_ranges| {
+            let mut vertical_ranges = vertical_ranges.clone()?;
+            vertical_ranges.update(min_y, max_y, |value| value.map(|_| FillState::Open));
+            Some(vertical_ranges)
+        });
+    }
+
+    // Adds the rect to the work region as open area. Area already in the
+    // region keeps whether it is filled.
+    pub fn extend_region(&mut self, min_x: f64, min_y: f64, max_x: f64, max_y: f64) {
+        if max_x < min_x {
+            return self.extend_region(max_x, min_y, min_x, max_y);
+        }
+        if max_y < min_y {
+            return self.extend_region(min_x, max_y, max_x, min_y);
+        }
+
+        self.map.update(min_x, max_x, |vertical_ranges| {
+            let mut vertical_ranges = vertical_ranges.clone()
+                .unwrap_or_else(|| PersistentRangeMap::from(None));
+            vertical_ranges.update(min_y, max_y, |value| Some(value.unwrap_or(FillState::Open)));
+            Some(vertical_ranges)
+        });
+    }
+
     pub fn get_open_rects(&self) -> Vec<(f64, f64, f64, f64)> {
         let mut r = Vec::new();
 
@@ -204,7 +386,7 @@ impl FillRect {
 
             for (value, min_y, max_y) in range_map.data() {
                 let Some(value) = value else { continue };
-                if value || (max_x - min_x) * (max_y - min_y) <= 0.0 { continue; }
+                if value == FillState::Filled || (max_x - min_x) * (max_y - min_y) <= 0.0 { continue; }
 
                 r.push((min_x, min_y, max_x, max_y));
             }
@@ -213,11 +395,156 @@ impl FillRect {
         return r;
     }
 
-    pub fn get_ys(&self, x: f64) -> Option<&Vec<f64>> {
-        if let Some(range) = self.map.value(x) {
-            Some(range.get_ranges())
-        } else {
-            None
+    // Largest open rectangle (min_x, min_y, max_x, max_y, area) inside the
+    // window, which may span several columns. Cells outside of the work region
+    // are never open.
+    pub fn largest_empty_rectangle(
+        &self,
+        min_x: f64, min_y: f64,
+        max_x: f64, max_y: f64,
+    ) -> Option<(f64, f64, f64, f64, f64)> {
+        let grid = self.open_grid(min_x, min_y, max_x, max_y);
+        let columns = grid.open.len();
+        let rows = grid.ys.len().saturating_sub(1);
+
+        let mut best: Option<(f64, f64, f64, f64, f64)> = None;
+        // first row of the open run ending at the current row, per column
+        let mut starts = vec![0; columns];
+        for row in 0..rows {
+            let top = grid.ys[row + 1];
+            for (start, open) in starts.iter_mut().zip(&grid.open) {
+                if !open[row] {
+                    *start = row + 1;
+                }
+            }
+            let height = |i: usize| top - grid.ys[starts[i]];
+
+            // largest rectangle under the histogram of open run heights
+            let mut stack: Vec<usize> = Vec::new();
+            for i in 0..=columns {
+                let h = if i < columns { height(i) } else { 0.0 };
+                while let Some(&last) = stack.last() {
+                    if height(last) < h {
+                        break;
+                    }
+                    stack.pop();
+                    let bar_height = height(last);
+                    let left = stack.last().map_or(0, |l| l + 1);
+                    let area = (grid.xs[i] - grid.xs[left]) * bar_height;
+                    if area > 0.0 && best.is_none_or(|b| b.4 < area) {
+                        best = Some((grid.xs[left], top - bar_height, grid.xs[i], top, area));
+                    }
+                }
+                stack.push(i);
+            }
+        }
+
+        return best;
+    }
+
+    // Lower left corner where a width by height rectangle fits in open cells
+    // of the window, exact fits included.
+    pub fn find_placement(
+        &self,
+        width: f64, height: f64,
+        within: (f64, f64, f64, f64),
+        strategy: PlacementStrategy,
+    ) -> Option<(f64, f64)> {
+        if !(width > 0.0 && height > 0.0) {
+            return None;
+        }
+        let (min_x, min_y, max_x, max_y) = within;
+        let grid = self.open_grid(min_x, min_y, max_x, max_y);
+        if grid.xs.len() < 2 || grid.ys.len() < 2 {
+            return None;
+        }
+        let blocked = grid.blocked_prefix();
+        let is_open = |i0: usize, i1: usize, j0: usize, j1: usize| {
+            blocked[i1][j1] + blocked[i0][j0] == blocked[i0][j1] + blocked[i1][j0]
+        };
+        let columns = grid.xs.len() - 1;
+        let rows = grid.ys.len() - 1;
+
+        let mut best: Option<(f64, f64, f64)> = None;
+        for j0 in 0..rows {
+            let y = grid.ys[j0];
+            let top = y + height - PLACEMENT_EPSILON;
+            if grid.ys[rows] < top {
+                break;
+            }
+            let j1 = grid.ys.partition_point(|v| *v < top);
+            for i0 in 0..columns {
+                let x = grid.xs[i0];
+                let right = x + width - PLACEMENT_EPSILON;
+                if grid.xs[columns] < right {
+                    break;
+                }
+                let i1 = grid.xs.partition_point(|v| *v < right);
+                if !is_open(i0, i1, j0, j1) {
+                    continue;
+                }
+
+                match strategy {
+                    PlacementStrategy::BottomLeft => return Some((x, y)),
+                    PlacementStrategy::BestAreaFit => {
+                        let mut right = i1;
+                        while right < columns && is_open(i0, right + 1, j0, j1) {
+                            right += 1;
+                        }
+                        let mut top = j1;
+                        while top < rows && is_open(i0, right, j0, top + 1) {
+                            top += 1;
+                        }
+                        let area = (grid.xs[right] - x) * (grid.ys[top] - y);
+                        if best.is_none_or(|b| area < b.2) {
+                            best = Some((x, y, area));
+                        }
+                    },
+                }
+            }
+        }
+
+        return best.map(|(x, y, _)| (x, y));
+    }
+
+    pub(crate) fn open_grid(&self, min_x: f64, min_y: f64, max_x: f64, max_y: f64) -> OpenGrid {
+        let columns = self.map.data_range_clipped(min_x, max_x);
+
+        let mut xs = Vec::with_capacity(columns.len() + 1);
+        let mut ys = vec![min_y, max_y];
+        for (column, local_min_x, _) in columns.iter() {
+            xs.push(*local_min_x);
+            let Some(column) = column else { continue };
+            for y in column.get_ranges() {
+                if min_y < *y && *y < max_y {
+                    ys.push(*y);
+                }
+            }
+        }
+        if let Some((_, _, local_max_x)) = columns.last() {
+            xs.push(*local_max_x);
+        }
+        ys.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        ys.dedup();
+        if max_y <= min_y {
+            ys.clear();
+        }
+
+        let mut open = Vec::with_capacity(columns.len());
+        for (column, _, _) in columns.iter() {
+            let mut cells = vec![false; ys.len().saturating_sub(1)];
+            if let Some(column) = column {
+                for j in 0..cells.len() {
+                    cells[j] = *column.value(ys[j]) == Some(FillState::Open);
+                }
+            }
+            open.push(cells);
+        }
+
+        OpenGrid {
+            xs,
+            ys,
+            open,
         }
     }
 }
@@ -266,6 +593,244 @@ mod test {
         assert_eq!(fill_rect.get_open_rects(), vec![(16.0, 100.0, 20.0, 110.0)]);
     }
 
+    #[test]
+    pub fn snapshot() {
+        let mut fill_rect = FillRect::from(10.0, 100.0, 20.0, 110.0);
+        fill_rect.fill_rect(10.0, 100.0, 12.0, 110.0);
+
+        let snapshot = fill_rect.clone();
+        fill_rect.fill_rect(12.0, 100.0, 20.0, 110.0);
+        assert_eq!(fill_rect.get_open_rects(), vec![]);
+        assert_eq!(snapshot.get_open_rects(), vec![(12.0, 100.0, 20.0, 110.0)]);
+
+        fill_rect = snapshot.clone();
+        assert_eq!(fill_rect, snapshot);
+        assert_eq!(fill_rect.is_fill(12.0, 100.0, 20.0, 110.0), false);
+    }
+
+    #[test]
+    pub fn largest_empty_rectangle() {
+        let mut fill_rect = FillRect::from(0.0, 0.0, 10.0, 10.0);
+        assert_eq!(
+            fill_rect.largest_empty_rectangle(0.0, 0.0, 10.0, 10.0),
+            Some((0.0, 0.0, 10.0, 10.0, 100.0)),
+        );
+
+        // three columns, open across all of them from y = 0 to 6
+        fill_rect.fill_rect(0.0, 6.0, 3.0, 10.0);
+        fill_rect.fill_rect(3.0, 8.0, 6.0, 10.0);
+        fill_rect.fill_rect(6.0, 7.0, 10.0, 10.0);
+        assert_eq!(
+            fill_rect.largest_empty_rectangle(0.0, 0.0, 10.0, 10.0),
+            Some((0.0, 0.0, 10.0, 6.0, 60.0)),
+        );
+        assert_eq!(fill_rect.biggest_unfilled_area_block(0.0, 0.0, 10.0, 10.0), 28.0);
+
+        assert_eq!(
+            fill_rect.largest_empty_rectangle(3.0, 0.0, 10.0, 10.0),
+            Some((3.0, 0.0, 10.0, 7.0, 49.0)),
+        );
+        assert_eq!(
+            fill_rect.largest_empty_rectangle(1.0, 1.0, 2.0, 2.0),
+            Some((1.0, 1.0, 2.0, 2.0, 1.0)),
+        );
+        assert_eq!(fill_rect.largest_empty_rectangle(0.0, 8.0, 10.0, 10.0), None);
+        assert_eq!(fill_rect.largest_empty_rectangle(20.0, 0.0, 30.0, 10.0), None);
+    }
+
+    #[test]
+    pub fn find_placement() {
+        let mut fill_rect = FillRect::from(0.0, 0.0, 10.0, 10.0);
+        fill_rect.fill_rect(0.0, 0.0, 4.0, 2.0);
+        fill_rect.fill_rect(4.0, 0.0, 7.0, 5.0);
+        fill_rect.fill_rect(0.0, 9.0, 10.0, 10.0);
+        let within = (0.0, 0.0, 10.0, 10.0);
+
+        assert_eq!(
+            fill_rect.find_placement(3.0, 3.0, within, PlacementStrategy::BottomLeft),
+            Some((7.0, 0.0)),
+        );
+        // spans the first two columns, and only fits exactly
+        assert_eq!(
+            fill_rect.find_placement(10.0, 4.0, within, PlacementStrategy::BottomLeft),
+            Some((0.0, 5.0)),
+        );
+        assert_eq!(
+            fill_rect.find_placement(10.0, 4.1, within, PlacementStrategy::BottomLeft),
+            None,
+        );
+        assert_eq!(
+            fill_rect.find_placement(4.0, 2.0, within, PlacementStrategy::BottomLeft),
+            Some((0.0, 2.0)),
+        );
+        assert_eq!(
+            fill_rect.find_placement(1.0, 1.0, within, PlacementStrategy::BottomLeft),
+            Some((7.0, 0.0)),
+        );
+        // the 3 by 4 corner above the second fill
+        assert_eq!(
+            fill_rect.find_placement(1.0, 1.0, within, PlacementStrategy::BestAreaFit),
+            Some((7.0, 5.0)),
+        );
+        assert_eq!(
+            fill_rect.find_placement(1.0, 1.0, (20.0, 20.0, 30.0, 30.0), PlacementStrategy::BottomLeft),
+            None,
+        );
+    }
+
+    #[test]
+    pub fn find_placement_exact_fit() {
+        let mut fill_rect = FillRect::from(0.0, 0.0, 1.0, 1.0);
+        fill_rect.fill_rect(0.3, 0.0, 1.0, 0.7);
+        let within = (0.0, 0.0, 1.0, 1.0);
+        // 0.1 + 0.2 rounds to just over the 0.3 wide gap
+        assert_eq!(
+            fill_rect.find_placement(0.1 + 0.2, 0.7, within, PlacementStrategy::BottomLeft),
+            Some((0.0, 0.0)),
+        );
+        assert_eq!(
+            fill_rect.find_placement(0.31, 0.2, within, PlacementStrategy::BottomLeft),
+            Some((0.0, 0.7)),
+        );
+        // the 0.7 by 0.3 strip above the fill
+        assert_eq!(
+            fill_rect.find_placement(0.1 + 0.2, 0.1 + 0.2, within, PlacementStrategy::BestAreaFit),
+            Some((0.3, 0.7)),
+        );
+    }
+
+    #[test]
+    pub fn unfill_rect() {
+        let mut fill_rect = FillRect::from(10.0, 100.0, 20.0, 110.0);
+        fill_rect.fill_rect(10.0, 100.0, 20.0, 110.0);
+        fill_rect.unfill_rect(5.0, 104.0, 12.0, 106.0);
+        assert_eq!(fill_rect.get_open_rects(), vec![(10.0, 104.0, 12.0, 106.0)]);
+
+        fill_rect.unfill_rect(12.0, 104.0, 20.0, 106.0);
+        assert_eq!(fill_rect.get_open_rects(), vec![(10.0, 104.0, 20.0, 106.0)]);
+    }
+
+    #[test]
+    pub fn extend_region() {
+        let mut fill_rect = FillRect::from(10.0, 100.0, 20.0, 110.0);
+        fill_rect.fill_rect(10.0, 100.0, 15.0, 110.0);
+        fill_rect.extend_region(12.0, 105.0, 25.0, 115.0);
+        assert_eq!(fill_rect.get_open_rects(), vec![
+            (12.0, 110.0, 15.0, 115.0),
+            (15.0, 100.0, 20.0, 115.0),
+            (20.0, 105.0, 25.0, 115.0),
+        ]);
+
+        fill_rect.fill_rect(20.0, 100.0, 25.0, 115.0);
+        assert_eq!(fill_rect.is_fill(20.0, 105.0, 25.0, 115.0), true);
+    }
+
+    #[test]
+    pub fn from_polygon() {
+        let fill_rect = FillRect::from_polygon(&[
+            (0.0, 0.0), (10.0, 0.0), (10.0, 4.0), (4.0, 4.0), (4.0, 10.0), (0.0, 10.0),
+        ], 1.0);
+        assert_eq!(fill_rect.get_open_rects(), vec![
+            (0.0, 0.0, 4.0, 10.0),
+            (4.0, 0.0, 10.0, 4.0),
+        ]);
+        assert_eq!(fill_rect, FillRect::from_polygon(&[
+            (0.0, 10.0), (0.0, 0.0), (10.0, 0.0), (10.0, 4.0), (4.0, 4.0), (4.0, 10.0),
+        ], 1.0));
+    }
+
+    #[test]
+    pub fn from_polygon_slanted() {
+        let fill_rect = FillRect::from_polygon(&[(0.0, 0.0), (4.0, 0.0), (0.0, 4.0)], 1.0);
+        assert_eq!(fill_rect.get_open_rects(), vec![
+            (0.0, 0.0, 1.0, 3.0),
+            (1.0, 0.0, 2.0, 2.0),
+            (2.0, 0.0, 3.0, 1.0),
+        ]);
+    }
+
+    #[test]
+    pub fn from_polygons_with_holes() {
+        let mut fill_rect = FillRect::from_polygons_with_holes(
+            &[vec![(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)]],
+            &[vec![(4.0, 4.0), (6.0, 4.0), (6.0, 6.0), (4.0, 6.0)]],
+            1.0,
+        );
+        assert_eq!(fill_rect.is_fill(4.0, 4.0, 6.0, 6.0), true);
+        assert_eq!(fill_rect.get_open_rects(), vec![
+            (0.0, 0.0, 4.0, 10.0),
+            (4.0, 0.0, 6.0, 4.0),
+            (4.0, 6.0, 6.0, 10.0),
+            (6.0, 0.0, 10.0, 10.0),
+        ]);
+        fill_rect.fill_rect(0.0, 0.0, 10.0, 10.0);
+        assert_eq!(fill_rect.get_open_rects(), vec![]);
+    }
+
+    #[test]
+    pub fn fill_circle() {
+        let mut fill_rect = FillRect::from(0.0, 0.0, 10.0, 10.0);
+        fill_rect.fill_rect(0.0, 0.0, 3.0, 10.0);
+        fill_rect.fill_circle(5.0, 5.0, 2.0, 0.1);
+
+        assert_eq!(fill_rect.is_fill(4.0, 4.0, 6.0, 6.0), true);
+        assert_eq!(fill_rect.is_fill(6.5, 6.5, 7.0, 7.0), false);
+        assert_eq!(fill_rect.is_fill(7.0, 4.9, 7.1, 5.1), false);
+        // the column boundary at x = 3 is kept
+        assert_eq!(fill_rect.get_ys(3.0).unwrap(), &vec![0.0, 10.0]);
+    }
+
+    #[test]
+    pub fn fill_circle_never_exceeds_shape() {
+        let mut fill_rect = FillRect::from(0.0, 0.0, 10.0, 10.0);
+        fill_rect.fill_circle(5.0, 5.0, 3.0, 0.25);
+
+        let open_area: f64 = fill_rect.get_open_rects().iter()
+            .map(|(min_x, min_y, max_x, max_y)| (max_x - min_x) * (max_y - min_y))
+            .sum();
+        let filled_area = 100.0 - open_area;
+        assert!(filled_area <= std::f64::consts::PI * 9.0);
+        assert!(filled_area > std::f64::consts::PI * 9.0 * 0.9);
+        assert_eq!(fill_rect.is_fill(2.0, 2.0, 2.5, 2.5), false);
+    }
+
+    #[test]
+    pub fn fill_polygon() {
+        let mut fill_rect = FillRect::from(0.0, 0.0, 10.0, 10.0);
+        fill_rect.fill_polygon(&[(0.0, 0.0), (4.0, 0.0), (0.0, 4.0)], 1.0);
+        assert_eq!(fill_rect.is_fill(0.0, 0.0, 1.0, 3.0), true);
+        assert_eq!(fill_rect.is_fill(1.0, 0.0, 2.0, 2.0), true);
+        assert_eq!(fill_rect.is_fill(2.0, 0.0, 3.0, 1.0), true);
+        assert_eq!(fill_rect.is_fill(0.0, 0.0, 1.0, 3.5), false);
+        assert_eq!(fill_rect.is_fill(3.0, 0.0, 4.0, 0.5), false);
+    }
+
+    #[test]
+    pub fn fill_capsule() {
+        let mut fill_rect = FillRect::from(0.0, 0.0, 10.0, 10.0);
+        fill_rect.fill_capsule_with_tolerance((2.0, 5.0), (8.0, 5.0), 1.0, 0.1);
+        assert_eq!(fill_rect.is_fill(2.0, 4.0, 8.0, 6.0), true);
+        assert_eq!(fill_rect.is_fill(1.1, 4.9, 1.2, 5.1), true);
+        assert_eq!(fill_rect.is_fill(0.0, 4.0, 1.0, 6.0), false);
+        assert_eq!(fill_rect.is_fill(1.0, 4.0, 1.5, 4.5), false);
+
+        let mut fill_rect = FillRect::from(0.0, 0.0, 10.0, 10.0);
+        fill_rect.fill_capsule((2.0, 5.0), (2.0, 8.0), 1.0);
+        assert_eq!(fill_rect.is_fill(1.0, 5.0, 3.0, 8.0), true);
+        assert_eq!(fill_rect.is_fill(1.9, 4.1, 2.1, 8.9), true);
+        assert_eq!(fill_rect.is_fill(1.0, 4.0, 1.2, 4.2), false);
+        // never more than the capsule itself
+        assert!(fill_rect.open_area() >= 100.0 - (6.0 + std::f64::consts::PI));
+    }
+
+    #[test]
+    pub fn bounds() {
+        let mut fill_rect = FillRect::from(10.0, 100.0, 20.0, 110.0);
+        assert_eq!(fill_rect.bounds(), Some((10.0, 100.0, 20.0, 110.0)));
+        fill_rect.extend_region(25.0, 90.0, 30.0, 95.0);
+        assert_eq!(fill_rect.bounds(), Some((10.0, 90.0, 30.0, 110.0)));
+    }
+
     #[test]
     pub fn test_gcode_package_end_line_error() {
         let mut fill_rect = FillRect::from(1.0, 1.0, 7.0, 7.0);
@@ -451,5 +1016,33 @@ mod test {
         assert_eq!(fill_rect.is_fill(6.999999999999992, 1.0, 7.0, 7.0), true);
         assert_eq!(fill_rect.is_fill(6.999999999999992, 1.0, 7.099999999999992, 7.0), true);
     }
-}
 
+    #[test]
+    pub fn snapshot_try_restore() {
+        let mut fill_rect = FillRect::from(0.0, 0.0, 200.0, 10.0);
+        for x in 0..200 {
+            fill_rect.fill_rect(x as f64, 0.0, x as f64 + 1.0, (x % 7) as f64 + 1.0);
+        }
+        let start = fill_rect.clone();
+
+        for i in 0..1000 {
+            let snapshot = fill_rect.clone();
+            let x = (i % 200) as f64 + 0.25;
+            fill_rect.fill_rect(x, 8.0, x + 0.5, 9.0);
+
+            // the outer vector is copied but only the column written to is new
+            let unshared = fill_rect.map.data().into_iter()
+                .filter(|(column, min_x, max_x)| {
+                    match (column, snapshot.map.value((min_x + max_x) / 2.0)) {
+                        (Some(column), Some(before)) => !column.shares_with(before),
+                        _ => false,
+                    }
+                })
+                .count();
+            assert_eq!(unshared, 1);
+
+            fill_rect = snapshot;
+        }
+        assert_eq!(fill_rect, start);
+    }
+}
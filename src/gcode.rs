@@ -0,0 +1,456 @@
+use std::f64::consts::PI;
+use std::fmt;
+
+use super::fill_rect::FillRect;
+
+// Lengths are in millimetres, G20 programs are converted on the way in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GcodeOptions {
+    pub tool_diameter: f64,
+    // the tool cuts while at or below this height
+    pub cut_z: f64,
+    // widest strip used to sweep the tool, and furthest an arc is flattened
+    pub tolerance: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GcodeError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for GcodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for GcodeError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Motion {
+    Rapid,
+    Linear,
+    ClockwiseArc,
+    CounterClockwiseArc,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GcodeMove {
+    pub line: usize,
+    pub motion: Motion,
+    pub from: (f64, f64, f64),
+    pub to: (f64, f64, f64),
+    // arcs only
+    pub center: Option<(f64, f64)>,
+}
+
+// Line numbers (starting at 1) of cutting moves
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct GcodeReport {
+    pub cutting_moves: usize,
+    // the tool left the bounds of the work region
+    pub out_of_bounds: Vec<usize>,
+    // nothing that was open got filled
+    pub no_new_cut: Vec<usize>,
+    // G0 moves that went below cut_z, which usually means a crash
+    pub rapid_cuts: Vec<usize>,
+}
+
+const INCH: f64 = 25.4;
+
+fn error(line: usize, message: String) -> GcodeError {
+    GcodeError {
+        line,
+        message,
+    }
+}
+
+fn words(line_number: usize, line: &str) -> Result<Vec<(char, f64)>, GcodeError> {
+    let mut code = String::new();
+    let mut in_comment = false;
+    for c in line.chars() {
+        match c {
+            ';' if !in_comment => break,
+            '(' => in_comment = true,
+            ')' => in_comment = false,
+            _ if !in_comment => code.push(c),
+            _ => {},
+        }
+    }
+
+    let mut r = Vec::new();
+    let mut chars = code.chars().filter(|c| !c.is_whitespace()).peekable();
+    while let Some(letter) = chars.next() {
+        if !letter.is_ascii_alphabetic() {
+            return Err(error(line_number, format!("unexpected '{}'", letter)));
+        }
+        let mut number = String::new();
+        while let Some(c) = chars.peek() {
+            if c.is_ascii_alphabetic() {
+                break;
+            }
+            number.push(*c);
+            chars.next();
+        }
+        let Ok(value) = number.parse::<f64>() else {
+            return Err(error(line_number, format!("bad number '{}' for {}", number, letter)));
+        };
+        r.push((letter.to_ascii_uppercase(), value));
+    }
+    return Ok(r);
+}
+
+// Moves of a program in millimetres, absolute coordinates. The tool starts at
+// x = 0, y = 0 and an unknown (infinitely high) z. Codes other than
+// G0-G3, G17-G21, G90 and G91 are skipped, as are % delimiter lines. Arcs
+// are only supported in the XY plane, G18 and G19 arcs are an error.
+pub fn parse(source: &str) -> Result<Vec<GcodeMove>, GcodeError> {
+    let mut moves = Vec::new();
+    let mut position = (0.0, 0.0, f64::INFINITY);
+    let mut motion = None;
+    let mut absolute = true;
+    let mut scale = 1.0;
+    let mut xy_plane = true;
+
+    for (index, line) in source.lines().enumerate() {
+        let line_number = index + 1;
+        if line.trim_start().starts_with('%') {
+            continue;
+        }
+        let words = words(line_number, line)?;
+
+        let mut axes: [Option<f64>; 3] = [None; 3];
+        let mut offsets: [Option<f64>; 2] = [None; 2];
+        let mut radius = None;
+        for (letter, value) in words.iter() {
+            match letter {
+                // tenths, so G91.1 isn't taken for G91
+                'G' => match (value * 10.0).round() as i64 {
+                    0 => motion = Some(Motion::Rapid),
+                    10 => motion = Some(Motion::Linear),
+                    20 => motion = Some(Motion::ClockwiseArc),
+                    30 => motion = Some(Motion::CounterClockwiseArc),
+                    170 => xy_plane = true,
+                    180 | 190 => xy_plane = false,
+                    200 => scale = INCH,
+                    210 => scale = 1.0,
+                    900 => absolute = true,
+                    910 => absolute = false,
+                    _ => {},
+                },
+                'X' => axes[0] = Some(*value),
+                'Y' => axes[1] = Some(*value),
+                'Z' => axes[2] = Some(*value),
+                'I' => offsets[0] = Some(*value),
+                'J' => offsets[1] = Some(*value),
+                'R' => radius = Some(*value),
+                _ => {},
+            }
+        }
+        if axes.iter().all(|axis| axis.is_none()) {
+            continue;
+        }
+        let Some(motion) = motion else {
+            return Err(error(line_number, "move without a motion mode".to_string()));
+        };
+
+        let from = position;
+        let target = |axis: Option<f64>, current: f64| match axis {
+            Some(value) if absolute => value * scale,
+            Some(value) => current + value * scale,
+            None => current,
+        };
+        let to = (
+            target(axes[0], from.0),
+            target(axes[1], from.1),
+            target(axes[2], from.2),
+        );
+
+        let center = match motion {
+            Motion::Rapid | Motion::Linear => None,
+            Motion::ClockwiseArc | Motion::CounterClockwiseArc => {
+                if !xy_plane {
+                    return Err(error(line_number, "arc outside of the XY plane (G17)".to_string()));
+                }
+                if offsets[0].is_some() || offsets[1].is_some() {
+                    Some((
+                        from.0 + offsets[0].unwrap_or(0.0) * scale,
+                        from.1 + offsets[1].unwrap_or(0.0) * scale,
+                    ))
+                } else if let Some(radius) = radius {
+                    let clockwise = motion == Motion::ClockwiseArc;
+                    match arc_center(from, to, radius * scale, clockwise) {
+                        Some(center) => Some(center),
+                        None => return Err(error(line_number, "arc radius too small".to_string())),
+                    }
+                } else {
+                    return Err(error(line_number, "arc without I, J or R".to_string()));
+                }
+            },
+        };
+
+        moves.push(GcodeMove {
+            line: line_number,
+            motion,
+            from,
+            to,
+            center,
+        });
+        position = to;
+    }
+
+    return Ok(moves);
+}
+
+fn arc_center(
+    from: (f64, f64, f64), to: (f64, f64, f64), radius: f64, clockwise: bool,
+) -> Option<(f64, f64)> {
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    let distance = (dx * dx + dy * dy).sqrt();
+    if distance == 0.0 || radius.abs() < distance / 2.0 - 1e-9 {
+        return None;
+    }
+    let height = (radius * radius - distance * distance / 4.0).max(0.0).sqrt();
+    // a positive radius takes the short way round
+    let side = if clockwise == (radius > 0.0) { -1.0 } else { 1.0 };
+    Some((
+        from.0 + dx / 2.0 - side * height * dy / distance,
+        from.1 + dy / 2.0 + side * height * dx / distance,
+    ))
+}
+
+impl GcodeMove {
+    // Points along the move. Arcs are flattened so that no point of a chord
+    // is further than tolerance from the arc, returned as the second value.
+    pub fn points(&self, tolerance: f64) -> (Vec<(f64, f64, f64)>, f64) {
+        let Some(center) = self.center else {
+            return (vec![self.from, self.to], 0.0);
+        };
+        let radius = ((self.from.0 - center.0).powi(2) + (self.from.1 - center.1).powi(2)).sqrt();
+        let start = (self.from.1 - center.1).atan2(self.from.0 - center.0);
+        let end = (self.to.1 - center.1).atan2(self.to.0 - center.0);
+        let mut sweep = if self.motion == Motion::CounterClockwiseArc {
+            end - start
+        } else {
+            start - end
+        };
+        while sweep <= 1e-12 {
+            sweep += 2.0 * PI;
+        }
+
+        let step = if tolerance < radius {
+            2.0 * (1.0 - tolerance / radius).acos()
+        } else {
+            PI / 2.0
+        };
+        let pieces = (sweep / step).ceil().max(1.0) as usize;
+        let angle = sweep / pieces as f64;
+        let direction = if self.motion == Motion::CounterClockwiseArc { 1.0 } else { -1.0 };
+
+        let mut points = vec![self.from];
+        for piece in 1..pieces {
+            let t = piece as f64 / pieces as f64;
+            let a = start + direction * angle * piece as f64;
+            points.push((
+                center.0 + radius * a.cos(),
+                center.1 + radius * a.sin(),
+                self.from.2 + (self.to.2 - self.from.2) * t,
+            ));
+        }
+        points.push(self.to);
+        (points, radius * (1.0 - (angle / 2.0).cos()))
+    }
+}
+
+impl FillRect {
+    // Fills everything the tool cuts while running the program. A move cuts
+    // when it starts and ends at or below cut_z, rapids included.
+    pub fn apply_gcode(&mut self, source: &str, options: &GcodeOptions) -> Result<GcodeReport, GcodeError> {
+        let moves = parse(source)?;
+        let bounds = self.bounds();
+        let radius = options.tool_diameter / 2.0;
+
+        let mut report = GcodeReport::default();
+        for gcode_move in moves {
+            if options.cut_z < gcode_move.from.2 || options.cut_z < gcode_move.to.2 {
+                continue;
+            }
+            let (points, sagitta) = gcode_move.points(options.tolerance);
+            let cuts: Vec<((f64, f64), (f64, f64))> = points.windows(2)
+                .map(|pair| ((pair[0].0, pair[0].1), (pair[1].0, pair[1].1)))
+                .collect();
+            report.cutting_moves += 1;
+            if gcode_move.motion == Motion::Rapid {
+                report.rapid_cuts.push(gcode_move.line);
+            }
+
+            let outside = |(x, y): (f64, f64)| match bounds {
+                Some((min_x, min_y, max_x, max_y)) =>
+                    x - radius < min_x || y - radius < min_y || max_x < x + radius || max_y < y + radius,
+                None => true,
+            };
+            if cuts.iter().any(|(a, b)| outside(*a) || outside(*b)) {
+                report.out_of_bounds.push(gcode_move.line);
+            }
+
+            let (min_x, min_y, max_x, max_y) = cuts.iter().fold(
+                (f64::INFINITY, f64::INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY),
+                |w, (a, b)| (
+                    w.0.min(a.0).min(b.0), w.1.min(a.1).min(b.1),
+                    w.2.max(a.0).max(b.0), w.3.max(a.1).max(b.1),
+                ),
+            );
+            let window = (min_x - radius, min_y - radius, max_x + radius, max_y + radius);
+            let before = self.open_area_in(window.0, window.1, window.2, window.3);
+            for &(a, b) in cuts.iter() {
+                self.fill_capsule_with_tolerance(a, b, radius - sagitta, options.tolerance);
+            }
+            let after = self.open_area_in(window.0, window.1, window.2, window.3);
+            // a second pass lands its strips differently and leaves slivers,
+            // which each strip keeps to about tolerance / tool_diameter of
+            // the area it sweeps
+            let swept: f64 = cuts.iter()
+                .map(|(a, b)| 2.0 * radius * (b.0 - a.0).hypot(b.1 - a.1) + PI * radius * radius)
+                .sum();
+            if before - after <= options.tolerance / options.tool_diameter * swept {
+                report.no_new_cut.push(gcode_move.line);
+            }
+        }
+
+        return Ok(report);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const OPTIONS: GcodeOptions = GcodeOptions {
+        tool_diameter: 2.0,
+        cut_z: 0.0,
+        tolerance: 0.05,
+    };
+
+    #[test]
+    pub fn test_parse() {
+        let moves = parse("
+            G21 G90 (metric, absolute)
+            G0 X1 Y2 Z5
+            G1 Z-1 F100 ; plunge
+            G91 G1 X2
+            G20 G1 Y1
+        ").unwrap();
+        let ends: Vec<(f64, f64, f64)> = moves.iter().map(|m| m.to).collect();
+        assert_eq!(ends, vec![
+            (1.0, 2.0, 5.0),
+            (1.0, 2.0, -1.0),
+            (3.0, 2.0, -1.0),
+            (3.0, 27.4, -1.0),
+        ]);
+        assert_eq!(moves[0].motion, Motion::Rapid);
+        assert_eq!(moves[1].line, 4);
+    }
+
+    #[test]
+    pub fn test_parse_arcs() {
+        let moves = parse("
+            G0 X10 Y0
+            G3 X0 Y10 I-10 J0
+            G2 X10 Y0 R10
+        ").unwrap();
+        assert_eq!(moves[1].center, Some((0.0, 0.0)));
+        let (x, y) = moves[2].center.unwrap();
+        assert!(x.abs() < 1e-9 && y.abs() < 1e-9);
+
+        let (points, sagitta) = moves[1].points(0.01);
+        assert!(sagitta <= 0.01);
+        for (x, y, _) in points {
+            assert!(((x * x + y * y).sqrt() - 10.0).abs() < 1e-9);
+            assert!(x >= -1e-9 && y >= -1e-9);
+        }
+    }
+
+    #[test]
+    pub fn test_parse_errors() {
+        assert_eq!(parse("G1 X1\nG1 X1.2.3").unwrap_err().line, 2);
+        assert_eq!(parse("X1").unwrap_err().line, 1);
+        assert_eq!(parse("G2 X1 Y1").unwrap_err().line, 1);
+    }
+
+    #[test]
+    pub fn test_apply_gcode() {
+        let mut fill_rect = FillRect::from(0.0, 0.0, 10.0, 10.0);
+        let report = fill_rect.apply_gcode("
+            G0 Z5
+            G0 X2 Y5
+            G1 Z-1
+            G1 X8
+            G1 X2
+            G0 Z5
+            G0 X20 Y20
+        ", &OPTIONS).unwrap();
+
+        // the plunge starts above cut_z so only the two passes cut
+        assert_eq!(report.cutting_moves, 2);
+        assert_eq!(report.out_of_bounds, vec![]);
+        assert_eq!(report.no_new_cut, vec![6]);
+        assert_eq!(fill_rect.is_fill(2.0, 4.5, 8.0, 5.5), true);
+        assert_eq!(fill_rect.is_fill(2.0, 6.5, 8.0, 7.0), false);
+    }
+
+    #[test]
+    pub fn test_apply_gcode_from_unknown_height() {
+        let mut fill_rect = FillRect::from(0.0, 0.0, 10.0, 10.0);
+        let report = fill_rect.apply_gcode("G0 X2 Y5\nG1 Z-1\nG1 X8", &OPTIONS).unwrap();
+        assert_eq!(report.cutting_moves, 1);
+        assert_eq!(fill_rect.is_fill(2.0, 4.5, 8.0, 5.5), true);
+    }
+
+    #[test]
+    pub fn test_apply_gcode_out_of_bounds() {
+        let mut fill_rect = FillRect::from(0.0, 0.0, 10.0, 10.0);
+        let report = fill_rect.apply_gcode("
+            G0 X5 Y5
+            G1 Z-1
+            G1 X12
+            G0 Z1
+            G1 X0 Y0
+        ", &OPTIONS).unwrap();
+        assert_eq!(report.cutting_moves, 1);
+        assert_eq!(report.out_of_bounds, vec![4]);
+        assert_eq!(fill_rect.is_fill(5.0, 4.5, 10.0, 5.5), true);
+    }
+
+    #[test]
+    pub fn test_apply_gcode_rapid_below_cut_z() {
+        let mut fill_rect = FillRect::from(0.0, 0.0, 10.0, 10.0);
+        let report = fill_rect.apply_gcode("G0 X1 Y5 Z-1\nG0 X9", &OPTIONS).unwrap();
+        assert_eq!(report.cutting_moves, 1);
+        assert_eq!(report.rapid_cuts, vec![2]);
+        assert_eq!(fill_rect.is_fill(1.0, 4.5, 9.0, 5.5), true);
+    }
+
+    #[test]
+    pub fn test_apply_gcode_ramp_out() {
+        let mut fill_rect = FillRect::from(0.0, 0.0, 10.0, 10.0);
+        let report = fill_rect.apply_gcode("G0 X2 Y5 Z1\nG0 Z-1\nG1 X5\nG1 X8 Z1", &OPTIONS).unwrap();
+        // only the pass at depth cuts, ramping out to above cut_z doesn't
+        assert_eq!(report.cutting_moves, 1);
+        assert_eq!(fill_rect.is_fill(2.0, 4.5, 5.0, 5.5), true);
+        assert_eq!(fill_rect.is_fill(6.0, 4.5, 8.0, 5.5), false);
+    }
+
+    #[test]
+    pub fn test_arc_planes() {
+        assert_eq!(parse("G18\nG2 X1 Z1 R1").unwrap_err().line, 2);
+        assert_eq!(parse("G19 G0 X1\nG17\nG2 X2 Y1 R1").unwrap().len(), 2);
+    }
+
+    #[test]
+    pub fn test_program_delimiters() {
+        let moves = parse("%\nG0 X1 Y2 Z5\nG1 Z-1\n%").unwrap();
+        assert_eq!(moves.len(), 2);
+        assert_eq!(moves[1].line, 3);
+    }
+}
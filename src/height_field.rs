@@ -0,0 +1,94 @@
+use super::fill_grid::FillGrid;
+
+// Height of the material left over every cell of the stock
+pub type HeightField = FillGrid<f64>;
+
+impl HeightField {
+    pub fn from(
+        min_x: f64, min_y: f64, max_x: f64, max_y: f64, height: f64
+    ) -> Self {
+        FillGrid::from_rect(height, min_x, min_y, max_x, max_y)
+    }
+
+    // Lowers the material under the rect to at most z
+    pub fn cut_rect(&mut self, min_x: f64, min_y: f64, max_x: f64, max_y: f64, z: f64) {
+        self.update_rect(min_x, min_y, max_x, max_y, |height| height.min(z));
+    }
+
+    // Highest material of the stock under the rect, None when the rect
+    // misses the stock
+    pub fn max_height_in(&self, min_x: f64, min_y: f64, max_x: f64, max_y: f64) -> Option<f64> {
+        let mut r: Option<f64> = None;
+        for (column, _, _) in self.map.data_range_address(min_x, max_x) {
+            let Some(column) = column else { continue };
+            for (height, _, _) in column.data_range_address(min_y, max_y) {
+                let Some(height) = height else { continue };
+                r = Some(r.map_or(*height, |r| r.max(*height)));
+            }
+        }
+        return r;
+    }
+
+    pub fn volume_above(&self, z: f64) -> f64 {
+        let mut r = 0.0;
+        for (height, min_x, min_y, max_x, max_y) in self.cells() {
+            if z < height {
+                r += (max_x - min_x) * (max_y - min_y) * (height - z);
+            }
+        }
+        return r;
+    }
+
+    // (min_x, min_y, max_x, max_y) of every cell with material above z
+    pub fn regions_above(&self, z: f64) -> Vec<(f64, f64, f64, f64)> {
+        let mut r = Vec::new();
+        for (height, min_x, min_y, max_x, max_y) in self.cells() {
+            if z < height && (max_x - min_x) * (max_y - min_y) > 0.0 {
+                r.push((min_x, min_y, max_x, max_y));
+            }
+        }
+        return r;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_cut_rect() {
+        let mut stock = HeightField::from(0.0, 0.0, 10.0, 10.0, 5.0);
+        stock.cut_rect(2.0, 2.0, 6.0, 6.0, 3.0);
+        stock.cut_rect(4.0, 4.0, 8.0, 8.0, 4.0);
+        assert_eq!(stock.value_at(5.0, 5.0), Some(&3.0));
+        assert_eq!(stock.value_at(7.0, 7.0), Some(&4.0));
+        assert_eq!(stock.value_at(9.0, 9.0), Some(&5.0));
+        assert_eq!(stock.value_at(11.0, 9.0), None);
+
+        assert_eq!(stock.max_height_in(2.0, 2.0, 6.0, 6.0), Some(3.0));
+        assert_eq!(stock.max_height_in(4.0, 4.0, 8.0, 8.0), Some(4.0));
+        assert_eq!(stock.max_height_in(0.0, 0.0, 10.0, 10.0), Some(5.0));
+        assert_eq!(stock.max_height_in(20.0, 0.0, 30.0, 10.0), None);
+    }
+
+    #[test]
+    pub fn test_volume_above() {
+        let mut stock = HeightField::from(0.0, 0.0, 10.0, 10.0, 5.0);
+        assert_eq!(stock.volume_above(0.0), 500.0);
+        stock.cut_rect(0.0, 0.0, 5.0, 10.0, 1.0);
+        assert_eq!(stock.volume_above(0.0), 300.0);
+        assert_eq!(stock.volume_above(2.0), 150.0);
+        assert_eq!(stock.volume_above(5.0), 0.0);
+    }
+
+    #[test]
+    pub fn test_regions_above() {
+        let mut stock = HeightField::from(0.0, 0.0, 10.0, 10.0, 5.0);
+        stock.cut_rect(0.0, 0.0, 5.0, 5.0, 1.0);
+        assert_eq!(stock.regions_above(2.0), vec![
+            (0.0, 5.0, 5.0, 10.0),
+            (5.0, 0.0, 10.0, 10.0),
+        ]);
+        assert_eq!(stock.regions_above(5.0), vec![]);
+    }
+}
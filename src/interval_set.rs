@@ -0,0 +1,287 @@
+use std::cmp::Ordering;
+
+use super::range_map::RangeMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct IntervalHandle(usize);
+
+// Stores possibly overlapping [min, max) intervals, each with a payload.
+//
+// The intervals are kept sorted by min and read as an implicit balanced
+// tree, where the node for [lo, hi) sits at (lo + hi) / 2 and max_ends holds
+// the largest max in its subtree. Queries skip every subtree that ends too
+// early, so containing and overlapping take O(log n + m log n) for m
+// results. get is O(1), insert and remove are O(n) as they shift the sorted
+// intervals and rebuild max_ends.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IntervalSet<K: PartialOrd + Copy, T> {
+    // (min, max, handle) sorted by min
+    intervals: Vec<(K, K, IntervalHandle)>,
+    max_ends: Vec<K>,
+    entries: Vec<Option<(T, K, K)>>,
+}
+
+fn compare<K: PartialOrd>(a: &K, b: &K) -> Ordering {
+    a.partial_cmp(b).unwrap_or(Ordering::Equal)
+}
+
+fn larger<K: PartialOrd + Copy>(a: K, b: K) -> K {
+    if a < b { b } else { a }
+}
+
+impl<K: PartialOrd + Copy, T> Default for IntervalSet<K, T> {
+    fn default() -> Self {
+        Self {
+            intervals: Vec::new(),
+            max_ends: Vec::new(),
+            entries: Vec::new(),
+        }
+    }
+}
+
+impl<K: PartialOrd + Copy, T> IntervalSet<K, T> {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.intervals.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.intervals.is_empty()
+    }
+
+    // [min, max)
+    pub fn insert(&mut self, payload: T, min: K, max: K) -> IntervalHandle {
+        let (min, max) = if max < min { (max, min) } else { (min, max) };
+        let handle = IntervalHandle(self.entries.len());
+        self.entries.push(Some((payload, min, max)));
+
+        let index = self.intervals.partition_point(|(local_min, _, _)| *local_min <= min);
+        self.intervals.insert(index, (min, max, handle));
+        self.rebuild_max_ends();
+        handle
+    }
+
+    pub fn remove(&mut self, handle: IntervalHandle) -> Option<T> {
+        let (payload, min, _) = self.entries.get_mut(handle.0)?.take()?;
+        let start = self.intervals.partition_point(|(local_min, _, _)| *local_min < min);
+        let offset = self.intervals[start..].iter().position(|(_, _, h)| *h == handle).unwrap();
+        self.intervals.remove(start + offset);
+        self.rebuild_max_ends();
+        Some(payload)
+    }
+
+    pub fn get(&self, handle: IntervalHandle) -> Option<(&T, K, K)> {
+        let (payload, min, max) = self.entries.get(handle.0)?.as_ref()?;
+        Some((payload, *min, *max))
+    }
+
+    // Every interval with min <= k < max
+    pub fn containing(&self, k: K) -> Vec<(IntervalHandle, &T, K, K)> {
+        let mut r = Vec::new();
+        self.search(0, self.intervals.len(), k, &|local_min| local_min <= k, &mut r);
+        return r;
+    }
+
+    // Every interval sharing some of [min, max)
+    pub fn overlapping(&self, min: K, max: K) -> Vec<(IntervalHandle, &T, K, K)> {
+        let mut r = Vec::new();
+        self.search(0, self.intervals.len(), min, &|local_min| local_min < max, &mut r);
+        return r;
+    }
+
+    pub fn data(&self) -> Vec<(IntervalHandle, &T, K, K)> {
+        self.intervals.iter()
+            .map(|(min, max, handle)| (*handle, self.payload(*handle), *min, *max))
+            .collect()
+    }
+
+    // Disjoint view where every piece holds resolve(payloads covering it).
+    // Points covered by no interval get resolve(&[]). Sweeps the bounds in
+    // order keeping the covering intervals, O(n log n) plus the total size
+    // of the slices handed to resolve.
+    pub fn flatten<V: PartialEq + Clone, F: Fn(&[&T]) -> V>(&self, resolve: F) -> RangeMap<K, V> {
+        let mut range_map = RangeMap::from(resolve(&[]));
+
+        let mut bounds = Vec::with_capacity(self.intervals.len() * 2);
+        for (min, max, _) in self.intervals.iter() {
+            bounds.push(*min);
+            bounds.push(*max);
+        }
+        bounds.sort_by(compare);
+        bounds.dedup_by(|a, b| compare(a, b) == Ordering::Equal);
+
+        // indices into intervals, kept in the same order
+        let mut active: Vec<usize> = Vec::new();
+        let mut next = 0;
+        for i in 1..bounds.len() {
+            let (min, max) = (bounds[i-1], bounds[i]);
+            active.retain(|index| min < self.intervals[*index].1);
+            while next < self.intervals.len() && self.intervals[next].0 <= min {
+                if min < self.intervals[next].1 {
+                    active.push(next);
+                }
+                next += 1;
+            }
+            let covering: Vec<&T> = active.iter()
+                .map(|index| self.payload(self.intervals[*index].2))
+                .collect();
+            range_map.set(resolve(&covering), min, max);
+        }
+
+        return range_map;
+    }
+
+    fn payload(&self, handle: IntervalHandle) -> &T {
+        &self.entries[handle.0].as_ref().unwrap().0
+    }
+
+    fn rebuild_max_ends(&mut self) {
+        self.max_ends = self.intervals.iter().map(|(_, max, _)| *max).collect();
+        self.build(0, self.intervals.len());
+    }
+
+    // Fills max_ends for the subtree over [lo, hi) and returns its max
+    fn build(&mut self, lo: usize, hi: usize) -> Option<K> {
+        if lo >= hi {
+            return None;
+        }
+        let mid = (lo + hi) / 2;
+        let mut max_end = self.max_ends[mid];
+        for child in [self.build(lo, mid), self.build(mid + 1, hi)].into_iter().flatten() {
+            max_end = larger(max_end, child);
+        }
+        self.max_ends[mid] = max_end;
+        Some(max_end)
+    }
+
+    // In order walk of the subtree over [lo, hi) collecting intervals that
+    // end after end_above and whose min passes starts_within. starts_within
+    // has to hold for a prefix of the sorted intervals.
+    fn search<'a, F: Fn(K) -> bool>(
+        &'a self, lo: usize, hi: usize, end_above: K, starts_within: &F,
+        r: &mut Vec<(IntervalHandle, &'a T, K, K)>,
+    ) {
+        if lo >= hi {
+            return;
+        }
+        let mid = (lo + hi) / 2;
+        if self.max_ends[mid] <= end_above {
+            return;
+        }
+        self.search(lo, mid, end_above, starts_within, r);
+        let (min, max, handle) = self.intervals[mid];
+        if !starts_within(min) {
+            return;
+        }
+        if end_above < max {
+            r.push((handle, self.payload(handle), min, max));
+        }
+        self.search(mid + 1, hi, end_above, starts_within, r);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_containing() {
+        let mut set = IntervalSet::new();
+        let a = set.insert("a", 0.0, 10.0);
+        let b = set.insert("b", 5.0, 15.0);
+        let c = set.insert("c", 20.0, 30.0);
+
+        let handles = |k| set.containing(k).into_iter().map(|(h, _, _, _)| h).collect::<Vec<_>>();
+        assert_eq!(handles(-1.0), vec![]);
+        assert_eq!(handles(0.0), vec![a]);
+        assert_eq!(handles(7.0), vec![a, b]);
+        assert_eq!(handles(10.0), vec![b]);
+        assert_eq!(handles(25.0), vec![c]);
+        assert_eq!(handles(30.0), vec![]);
+    }
+
+    #[test]
+    pub fn test_overlapping() {
+        let mut set = IntervalSet::new();
+        set.insert(1, 0.0, 10.0);
+        set.insert(2, 5.0, 15.0);
+        set.insert(3, 20.0, 30.0);
+
+        let payloads = |min, max| set.overlapping(min, max)
+            .into_iter()
+            .map(|(_, p, _, _)| *p)
+            .collect::<Vec<_>>();
+        assert_eq!(payloads(10.0, 20.0), vec![2]);
+        assert_eq!(payloads(-5.0, 0.0), vec![]);
+        assert_eq!(payloads(-5.0, 0.5), vec![1]);
+        assert_eq!(payloads(0.0, 100.0), vec![1, 2, 3]);
+    }
+
+    #[test]
+    pub fn test_remove() {
+        let mut set = IntervalSet::new();
+        let a = set.insert("a", 0.0, 10.0);
+        let b = set.insert("b", 5.0, 15.0);
+        assert_eq!(set.remove(a), Some("a"));
+        assert_eq!(set.remove(a), None);
+        assert_eq!(set.len(), 1);
+        assert_eq!(set.get(b), Some((&"b", 5.0, 15.0)));
+        assert_eq!(set.containing(7.0).len(), 1);
+    }
+
+    #[test]
+    pub fn test_flatten() {
+        let mut set = IntervalSet::new();
+        set.insert(1, 0.0, 10.0);
+        set.insert(2, 5.0, 15.0);
+        set.insert(4, 20.0, 30.0);
+
+        let count = set.flatten(|payloads| payloads.len());
+        assert_eq!(count.data(), vec![
+            (1, 0.0, 5.0),
+            (2, 5.0, 10.0),
+            (1, 10.0, 15.0),
+            (0, 15.0, 20.0),
+            (1, 20.0, 30.0),
+        ]);
+
+        let sum = set.flatten(|payloads| payloads.iter().copied().sum::<i32>());
+        assert_eq!(sum.value(7.0), &3);
+        assert_eq!(sum.value(17.0), &0);
+        assert_eq!(sum.value(25.0), &4);
+    }
+
+    #[test]
+    pub fn test_matches_scan() {
+        let mut set = IntervalSet::new();
+        let mut handles = Vec::new();
+        for i in 0..40 {
+            let min = ((i * 37) % 50) as f64;
+            let max = min + ((i * 13) % 17) as f64 + 1.0;
+            handles.push(set.insert(i, min, max));
+        }
+        for handle in handles.iter().step_by(3) {
+            set.remove(*handle);
+        }
+
+        let payloads = |r: Vec<(IntervalHandle, &i32, f64, f64)>| r.into_iter().map(|(_, p, _, _)| *p).collect::<Vec<_>>();
+        for k in 0..70 {
+            let k = k as f64 - 2.5;
+            let scan = set.data().into_iter()
+                .filter(|(_, _, min, max)| *min <= k && k < *max)
+                .collect();
+            assert_eq!(payloads(set.containing(k)), payloads(scan));
+
+            let scan = set.data().into_iter()
+                .filter(|(_, _, min, max)| *min < k + 4.0 && k < *max)
+                .collect();
+            assert_eq!(payloads(set.overlapping(k, k + 4.0)), payloads(scan));
+
+            let count = set.flatten(|payloads| payloads.len());
+            assert_eq!(*count.value(k), set.containing(k).len());
+        }
+    }
+}
@@ -0,0 +1,256 @@
+use super::fill_rect::FillRect;
+use super::range_fill::RangeFill;
+use super::range_map::RangeMap;
+
+pub trait Journaled {
+    type Patch;
+
+    // Applies the patch and returns the patch that reverts it
+    fn apply_patch(&mut self, patch: Self::Patch) -> Self::Patch;
+}
+
+// Values to write back over [min, max) pieces of a 1D map, in order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RangePatch<K: PartialOrd + Copy, V: PartialEq + Clone> {
+    pub pieces: Vec<(V, K, K)>,
+}
+
+pub(crate) fn apply_range_patch<K: PartialOrd + Copy, V: PartialEq + Clone>(
+    map: &mut RangeMap<K, V>,
+    patch: RangePatch<K, V>,
+) -> RangePatch<K, V> {
+    let mut inverse = Vec::new();
+    for (_, min, max) in patch.pieces.iter() {
+        inverse.append(&mut map.data_range_clipped(*min, *max));
+    }
+    for (value, min, max) in patch.pieces {
+        map.set(value, min, max);
+    }
+    RangePatch {
+        pieces: inverse,
+    }
+}
+
+impl<K: PartialOrd + Copy, V: PartialEq + Clone> Journaled for RangeMap<K, V> {
+    type Patch = RangePatch<K, V>;
+
+    fn apply_patch(&mut self, patch: Self::Patch) -> Self::Patch {
+        apply_range_patch(self, patch)
+    }
+}
+
+// Point in the history, as the undo depth and the sequence number of the
+// last edit before it (0 for none). Edits get numbers that are never
+// reused, so a mark whose edit was undone and then dropped by a new edit no
+// longer matches anything.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Mark {
+    depth: usize,
+    sequence: u64,
+}
+
+// Keeps the inverse of every edit made through it so they can be undone.
+#[derive(Debug, Clone)]
+pub struct Journal<T: Journaled> {
+    inner: T,
+    // (sequence, inverse patch)
+    undo: Vec<(u64, T::Patch)>,
+    redo: Vec<(u64, T::Patch)>,
+    last_sequence: u64,
+}
+
+impl<T: Journaled> From<T> for Journal<T> {
+    fn from(inner: T) -> Self {
+        Self {
+            inner,
+            undo: Vec::new(),
+            redo: Vec::new(),
+            last_sequence: 0,
+        }
+    }
+}
+
+impl<T: Journaled> Journal<T> {
+    pub fn from(inner: T) -> Self {
+        From::from(inner)
+    }
+
+    pub fn get(&self) -> &T {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    pub fn apply(&mut self, patch: T::Patch) {
+        let inverse = self.inner.apply_patch(patch);
+        self.record(inverse);
+    }
+
+    pub fn checkpoint(&self) -> Mark {
+        Mark {
+            depth: self.undo.len(),
+            sequence: self.undo.last().map_or(0, |(sequence, _)| *sequence),
+        }
+    }
+
+    // Undoes every edit made after the mark was taken. False, leaving
+    // everything as is, when the marked state is not behind the current one
+    // anymore: it was undone, or undone and then replaced by new edits.
+    pub fn rollback_to(&mut self, mark: Mark) -> bool {
+        let sequence = match mark.depth {
+            0 => Some(0),
+            depth => self.undo.get(depth - 1).map(|(sequence, _)| *sequence),
+        };
+        if sequence != Some(mark.sequence) {
+            return false;
+        }
+        while self.undo.len() > mark.depth {
+            self.undo();
+        }
+        true
+    }
+
+    pub fn undo(&mut self) -> bool {
+        let Some((sequence, patch)) = self.undo.pop() else { return false };
+        let inverse = self.inner.apply_patch(patch);
+        self.redo.push((sequence, inverse));
+        true
+    }
+
+    pub fn redo(&mut self) -> bool {
+        let Some((sequence, patch)) = self.redo.pop() else { return false };
+        let inverse = self.inner.apply_patch(patch);
+        self.undo.push((sequence, inverse));
+        true
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    fn record(&mut self, inverse: T::Patch) {
+        self.last_sequence += 1;
+        self.undo.push((self.last_sequence, inverse));
+        self.redo.clear();
+    }
+}
+
+impl<K: PartialOrd + Copy, V: PartialEq + Clone> Journal<RangeMap<K, V>> {
+    pub fn set(&mut self, value: V, min: K, max: K) {
+        self.apply(RangePatch {
+            pieces: vec![(value, min, max)],
+        });
+    }
+}
+
+impl Journal<RangeFill> {
+    pub fn fill(&mut self, min: f64, max: f64) {
+        self.apply(RangePatch {
+            pieces: vec![(true, min, max)],
+        });
+    }
+}
+
+impl Journal<FillRect> {
+    pub fn fill_rect(&mut self, min_x: f64, min_y: f64, max_x: f64, max_y: f64) {
+        let before = self.inner.clone();
+        self.inner.fill_rect(min_x, min_y, max_x, max_y);
+        self.record(before);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_range_map_undo_redo() {
+        let mut journal = Journal::from(RangeMap::from(0));
+        journal.set(1, 0.0, 10.0);
+        journal.set(2, 5.0, 15.0);
+        assert_eq!(journal.get().data(), vec![(1, 0.0, 5.0), (2, 5.0, 15.0)]);
+
+        assert!(journal.undo());
+        assert_eq!(journal.get().data(), vec![(1, 0.0, 10.0)]);
+        assert!(journal.undo());
+        assert_eq!(journal.get().data(), vec![]);
+        assert!(!journal.undo());
+
+        assert!(journal.redo());
+        assert!(journal.redo());
+        assert_eq!(journal.get().data(), vec![(1, 0.0, 5.0), (2, 5.0, 15.0)]);
+        assert!(!journal.redo());
+    }
+
+    #[test]
+    pub fn test_new_edit_clears_redo() {
+        let mut journal = Journal::from(RangeMap::from(0));
+        journal.set(1, 0.0, 10.0);
+        journal.undo();
+        assert!(journal.can_redo());
+        journal.set(2, 0.0, 1.0);
+        assert!(!journal.can_redo());
+    }
+
+    #[test]
+    pub fn test_range_fill_rollback() {
+        let mut journal = Journal::from(RangeFill::from(10.0, 20.0));
+        journal.fill(11.0, 12.0);
+        let mark = journal.checkpoint();
+        journal.fill(13.0, 14.0);
+        journal.fill(10.0, 20.0);
+        assert_eq!(journal.get().contains_unfilled(10.0, 20.0), false);
+
+        assert!(journal.rollback_to(mark));
+        assert_eq!(journal.get().get_ranges(), &vec![10.0, 11.0, 12.0, 20.0]);
+        assert!(journal.redo());
+        assert_eq!(journal.get().get_ranges(), &vec![10.0, 11.0, 12.0, 13.0, 14.0, 20.0]);
+    }
+
+    #[test]
+    pub fn test_fill_rect_undo() {
+        let start = FillRect::from(10.0, 100.0, 20.0, 110.0);
+        let mut journal = Journal::from(start.clone());
+        let mark = journal.checkpoint();
+        journal.fill_rect(10.0, 100.0, 15.0, 110.0);
+        journal.fill_rect(15.0, 100.0, 20.0, 105.0);
+        assert_eq!(journal.get().get_open_rects(), vec![(15.0, 105.0, 20.0, 110.0)]);
+
+        journal.undo();
+        assert_eq!(journal.get().get_open_rects(), vec![(15.0, 100.0, 20.0, 110.0)]);
+        assert!(journal.rollback_to(mark));
+        assert_eq!(journal.get(), &start);
+        journal.redo();
+        assert_eq!(journal.get().get_open_rects(), vec![(15.0, 100.0, 20.0, 110.0)]);
+    }
+
+    #[test]
+    pub fn test_stale_mark() {
+        let mut journal = Journal::from(RangeMap::from(0));
+        journal.set(1, 0.0, 1.0);
+        journal.set(2, 1.0, 2.0);
+        let mark = journal.checkpoint();
+        journal.undo();
+        journal.undo();
+        // reuses the depth the mark was taken at with other edits
+        journal.set(5, 0.0, 10.0);
+        journal.set(6, 0.0, 10.0);
+        journal.set(7, 0.0, 10.0);
+        assert!(!journal.rollback_to(mark));
+        assert_eq!(journal.get().data(), vec![(7, 0.0, 10.0)]);
+
+        // a mark that was undone past is stale as well
+        let mark = journal.checkpoint();
+        journal.undo();
+        assert!(!journal.rollback_to(mark));
+        assert!(journal.redo());
+        assert!(journal.rollback_to(mark));
+        assert_eq!(journal.get().data(), vec![(7, 0.0, 10.0)]);
+    }
+}
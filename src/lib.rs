@@ -1,10 +1,45 @@
 pub mod range_fill;
 pub mod range_map;
+pub mod persistent_range_map;
+pub mod fill_grid;
 pub mod fill_rect;
+pub mod height_field;
+pub mod fill_box;
+pub mod fill_n;
+pub mod coverage;
+pub mod morphology;
+pub mod nearest;
+pub mod raycast;
+pub mod clearance;
+pub mod range_count;
+pub mod interval_set;
+pub mod journal;
+mod polygon;
+pub mod toolpath;
+pub mod svg;
+pub mod raster;
+pub mod regions;
+pub mod contour;
+#[cfg(feature = "gcode")]
+pub mod gcode;
 
 pub use range_fill::*;
 pub use self::range_map::*;
+pub use self::persistent_range_map::*;
+pub use self::fill_grid::*;
 pub use self::fill_rect::*;
+pub use self::height_field::*;
+pub use self::fill_box::*;
+pub use self::fill_n::*;
+pub use self::range_count::*;
+pub use self::interval_set::*;
+pub use self::journal::*;
+pub use self::toolpath::*;
+pub use self::svg::*;
+pub use self::raster::*;
+pub use self::regions::*;
+pub use self::contour::*;
+pub use self::raycast::*;
 
 pub fn add(left: usize, right: usize) -> usize {
     left + right
@@ -0,0 +1,95 @@
+use super::fill_rect::{FillRect, FillState};
+
+impl FillRect {
+    // Every rect grown by dx to the sides and dy up and down is filled,
+    // within the work region
+    fn dilate(&self, rects: Vec<(f64, f64, f64, f64)>, dx: f64, dy: f64) -> FillRect {
+        let (dx, dy) = (dx.max(0.0), dy.max(0.0));
+        let mut r = self.clone();
+        for (min_x, min_y, max_x, max_y) in rects {
+            r.update_rect(min_x - dx, min_y - dy, max_x + dx, max_y + dy, |_| FillState::Filled);
+        }
+        return r;
+    }
+
+    // Copy where every filled cell is grown by dx and dy (the Minkowski sum
+    // with a 2dx by 2dy rect). The work region keeps its shape.
+    pub fn dilate_filled(&self, dx: f64, dy: f64) -> FillRect {
+        let filled = self.cells().into_iter()
+            .filter(|(state, _, _, _, _)| *state == FillState::Filled)
+            .map(|(_, min_x, min_y, max_x, max_y)| (min_x, min_y, max_x, max_y))
+            .collect();
+        self.dilate(filled, dx, dy)
+    }
+
+    // Copy where the open area shrinks by dx and dy away from everything
+    // that is not open, the outside of the work region included. What is
+    // left is where the center of a 2dx by 2dy tool fits.
+    pub fn erode_open(&self, dx: f64, dy: f64) -> FillRect {
+        let Some((bounds_min_x, bounds_min_y, bounds_max_x, bounds_max_y)) = self.bounds() else {
+            return self.clone();
+        };
+
+        let mut blocking = vec![
+            (bounds_min_x, bounds_min_y, bounds_min_x, bounds_max_y),
+            (bounds_max_x, bounds_min_y, bounds_max_x, bounds_max_y),
+            (bounds_min_x, bounds_min_y, bounds_max_x, bounds_min_y),
+            (bounds_min_x, bounds_max_y, bounds_max_x, bounds_max_y),
+        ];
+        for (column, min_x, max_x) in self.map.data_range_clipped(bounds_min_x, bounds_max_x) {
+            let Some(column) = column else {
+                blocking.push((min_x, bounds_min_y, max_x, bounds_max_y));
+                continue;
+            };
+            for (state, min_y, max_y) in column.data_range_clipped(bounds_min_y, bounds_max_y) {
+                if state != Some(FillState::Open) {
+                    blocking.push((min_x, min_y, max_x, max_y));
+                }
+            }
+        }
+        self.dilate(blocking, dx, dy)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_dilate_filled() {
+        let mut fill_rect = FillRect::from(0.0, 0.0, 10.0, 10.0);
+        fill_rect.fill_rect(4.0, 4.0, 6.0, 6.0);
+        let dilated = fill_rect.dilate_filled(1.0, 2.0);
+        assert_eq!(dilated.is_fill(3.0, 2.0, 7.0, 8.0), true);
+        assert_eq!(dilated.open_area(), 100.0 - 4.0 * 6.0);
+        assert_eq!(dilated.bounds(), fill_rect.bounds());
+        // the original is left alone
+        assert_eq!(fill_rect.open_area(), 96.0);
+    }
+
+    #[test]
+    pub fn test_dilate_stays_in_region() {
+        let mut fill_rect = FillRect::from(0.0, 0.0, 10.0, 10.0);
+        fill_rect.fill_rect(0.0, 0.0, 1.0, 1.0);
+        let dilated = fill_rect.dilate_filled(2.0, 2.0);
+        assert_eq!(dilated.total_area(), 100.0);
+        assert_eq!(dilated.filled_area(), 9.0);
+    }
+
+    #[test]
+    pub fn test_erode_open() {
+        let fill_rect = FillRect::from(0.0, 0.0, 10.0, 10.0);
+        let eroded = fill_rect.erode_open(1.0, 2.0);
+        assert_eq!(eroded.get_open_rects(), vec![(1.0, 2.0, 9.0, 8.0)]);
+
+        // notch cut out of the region
+        let fill_rect = FillRect::from_polygon(&[
+            (0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (6.0, 10.0), (6.0, 5.0), (4.0, 5.0), (4.0, 10.0), (0.0, 10.0),
+        ], 0.1);
+        let mut eroded = fill_rect.erode_open(1.0, 1.0);
+        assert_eq!(eroded.open_area(), 8.0 * 8.0 - 4.0 * 5.0);
+        assert_eq!(eroded.is_fill_point(5.0, 3.0), false);
+        assert_eq!(eroded.is_fill_point(3.5, 8.0), true);
+        assert_eq!(eroded.is_fill_point(2.5, 8.0), false);
+    }
+}
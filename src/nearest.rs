@@ -0,0 +1,162 @@
+use super::fill_rect::{FillRect, FillState};
+use super::range_map::RangeMap;
+
+// Piece p of the map, where piece 0 is the default before the first range,
+// pieces 1..=n are the values and piece n+1 is the default after the last.
+fn piece<V: PartialEq + Clone>(map: &RangeMap<f64, V>, p: usize) -> (&V, f64, f64) {
+    let (ranges, values) = (map.get_ranges(), map.get_values());
+    if ranges.is_empty() {
+        return (map.default_value(), f64::NEG_INFINITY, f64::INFINITY);
+    }
+    if p == 0 {
+        return (map.default_value(), f64::NEG_INFINITY, ranges[0]);
+    }
+    if p > values.len() {
+        return (map.default_value(), ranges[ranges.len()-1], f64::INFINITY);
+    }
+    return (&values[p-1], ranges[p-1], ranges[p]);
+}
+
+fn piece_count<V: PartialEq + Clone>(map: &RangeMap<f64, V>) -> usize {
+    if map.get_ranges().is_empty() { 1 } else { map.get_values().len() + 2 }
+}
+
+// Pieces in order of their distance from k, with that distance and the
+// closest coordinate of the piece to k. visit returns the best distance so
+// far and pieces at least that far away are skipped.
+fn pieces_outward<V: PartialEq + Clone, F: FnMut(&V, f64, f64) -> f64>(
+    map: &RangeMap<f64, V>, k: f64, mut visit: F,
+) {
+    let count = piece_count(map);
+    let containing = if count == 1 { 0 } else { algorithms::seen_before_or_equal(map.get_ranges(), k) };
+    let (value, _, _) = piece(map, containing);
+    let mut limit = visit(value, 0.0, k);
+    for p in (0..containing).rev() {
+        let (value, _, max) = piece(map, p);
+        if limit <= k - max {
+            break;
+        }
+        limit = limit.min(visit(value, k - max, max));
+    }
+    for p in containing + 1..count {
+        let (value, min, _) = piece(map, p);
+        if limit <= min - k {
+            break;
+        }
+        limit = limit.min(visit(value, min - k, min));
+    }
+}
+
+// Distance and coordinate of the closest piece to k holding a value that
+// matches
+fn nearest_in<V: PartialEq + Clone, F: Fn(&V) -> bool>(
+    map: &RangeMap<f64, V>, k: f64, matches: F,
+) -> Option<(f64, f64)> {
+    let mut best: Option<(f64, f64)> = None;
+    pieces_outward(map, k, |value, distance, at| {
+        if matches(value) && best.is_none_or(|b| distance < b.0) {
+            best = Some((distance, at));
+        }
+        best.map_or(f64::INFINITY, |b| b.0)
+    });
+    return best;
+}
+
+impl FillRect {
+    // Closest point (x, y, distance) of the open area, on the closure of the
+    // open cells. Columns are searched outward from x and the search stops
+    // once the next column is further away than the best point found.
+    pub fn nearest_open_point(&self, x: f64, y: f64) -> Option<(f64, f64, f64)> {
+        let mut best: Option<(f64, f64, f64)> = None;
+        pieces_outward(&self.map, x, |column, dx, at_x| {
+            if let Some(column) = column {
+                if let Some((dy, at_y)) = nearest_in(column, y, |state| *state == Some(FillState::Open)) {
+                    let distance = dx.hypot(dy);
+                    if best.is_none_or(|b| distance < b.2) {
+                        best = Some((at_x, at_y, distance));
+                    }
+                }
+            }
+            best.map_or(f64::INFINITY, |b| b.2)
+        });
+        return best;
+    }
+
+    // Distance to the closest point that is not open, where outside of the
+    // work region counts as filled like in is_fill_point. 0 on filled points.
+    pub fn distance_to_filled(&self, x: f64, y: f64) -> f64 {
+        let mut best = f64::INFINITY;
+        pieces_outward(&self.map, x, |column, dx, _| {
+            let dy = match column {
+                Some(column) => nearest_in(column, y, |state| *state != Some(FillState::Open))
+                    .map_or(f64::INFINITY, |(dy, _)| dy),
+                None => 0.0,
+            };
+            best = best.min(dx.hypot(dy));
+            best
+        });
+        return best;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_nearest_in() {
+        let mut map = RangeMap::from(0);
+        map.set(1, 0.0, 1.0);
+        map.set(2, 3.0, 4.0);
+        assert_eq!(nearest_in(&map, 0.5, |v| *v == 1), Some((0.0, 0.5)));
+        assert_eq!(nearest_in(&map, 2.5, |v| *v == 1), Some((1.5, 1.0)));
+        assert_eq!(nearest_in(&map, 2.5, |v| *v == 2), Some((0.5, 3.0)));
+        assert_eq!(nearest_in(&map, 2.5, |v| *v == 0), Some((0.0, 2.5)));
+        assert_eq!(nearest_in(&map, 0.5, |v| *v == 0), Some((0.5, 0.0)));
+        assert_eq!(nearest_in(&map, 0.5, |v| *v == 3), None);
+        assert_eq!(nearest_in(&RangeMap::<f64, usize>::from(0), 0.5, |v| *v == 0), Some((0.0, 0.5)));
+    }
+
+    #[test]
+    pub fn test_nearest_open_point() {
+        let mut fill_rect = FillRect::from(0.0, 0.0, 10.0, 10.0);
+        fill_rect.fill_rect(0.0, 0.0, 10.0, 10.0);
+        assert_eq!(fill_rect.nearest_open_point(5.0, 5.0), None);
+
+        fill_rect.unfill_rect(8.0, 1.0, 9.0, 2.0);
+        fill_rect.unfill_rect(1.0, 6.0, 2.0, 7.0);
+        assert_eq!(fill_rect.nearest_open_point(8.5, 1.5), Some((8.5, 1.5, 0.0)));
+        assert_eq!(fill_rect.nearest_open_point(5.0, 1.0), Some((8.0, 1.0, 3.0)));
+        assert_eq!(fill_rect.nearest_open_point(4.0, 7.0), Some((2.0, 7.0, 2.0)));
+        assert_eq!(fill_rect.nearest_open_point(-3.0, 2.0), Some((1.0, 6.0, 32.0_f64.sqrt())));
+    }
+
+    #[test]
+    pub fn test_nearest_open_point_matches_scan() {
+        let mut fill_rect = FillRect::from(0.0, 0.0, 10.0, 10.0);
+        fill_rect.fill_rect(2.0, 0.0, 7.0, 6.0);
+        fill_rect.fill_rect(0.0, 3.0, 4.0, 10.0);
+        fill_rect.fill_rect(6.0, 8.0, 10.0, 9.0);
+        for (x, y) in [(3.0_f64, 4.0_f64), (5.0, 1.0), (1.0, 8.0), (12.0, -3.0), (6.5, 8.5)] {
+            let scan = fill_rect.get_open_rects().into_iter()
+                .map(|(min_x, min_y, max_x, max_y)| {
+                    (x - x.max(min_x).min(max_x)).hypot(y - y.max(min_y).min(max_y))
+                })
+                .fold(f64::INFINITY, f64::min);
+            assert_eq!(fill_rect.nearest_open_point(x, y).unwrap().2, scan);
+        }
+    }
+
+    #[test]
+    pub fn test_distance_to_filled() {
+        let mut fill_rect = FillRect::from(0.0, 0.0, 10.0, 10.0);
+        fill_rect.fill_rect(4.0, 4.0, 6.0, 6.0);
+        assert_eq!(fill_rect.distance_to_filled(5.0, 5.0), 0.0);
+        assert_eq!(fill_rect.distance_to_filled(5.0, 2.5), 1.5);
+        assert_eq!(fill_rect.distance_to_filled(2.0, 2.0), 2.0);
+        assert_eq!(fill_rect.distance_to_filled(3.0, 3.0), 2.0_f64.sqrt());
+        // outside of the work region
+        assert_eq!(fill_rect.distance_to_filled(9.5, 5.0), 0.5);
+        assert_eq!(fill_rect.distance_to_filled(12.0, 5.0), 0.0);
+    }
+}
@@ -0,0 +1,128 @@
+use std::ops::Deref;
+use std::sync::Arc;
+
+use super::range_map::RangeMap;
+
+// A RangeMap behind an atomically counted pointer, so it stays Send and Sync
+// like the plain map. Cloning is O(1) and a clone only copies the ranges the
+// first time it is written to. Values that are themselves persistent keep
+// being shared, so the first write to a nested map after a clone copies the
+// outer vector of pointers, O(columns) pointer copies, and the one inner map
+// that changed.
+#[derive(Debug, Clone)]
+pub struct PersistentRangeMap<K: PartialOrd + Copy, V: PartialEq + Clone> {
+    map: Arc<RangeMap<K, V>>,
+}
+
+impl<K: PartialOrd + Copy, V: PartialEq + Clone> PartialEq for PersistentRangeMap<K, V> {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.map, &other.map) || self.map == other.map
+    }
+}
+
+impl<K: PartialOrd + Copy, V: PartialEq + Clone> From<V> for PersistentRangeMap<K, V> {
+    fn from(out_of_range_value: V) -> Self {
+        Self {
+            map: Arc::new(RangeMap::from(out_of_range_value)),
+        }
+    }
+}
+
+impl<K: PartialOrd + Copy, V: PartialEq + Clone> From<RangeMap<K, V>> for PersistentRangeMap<K, V> {
+    fn from(map: RangeMap<K, V>) -> Self {
+        Self {
+            map: Arc::new(map),
+        }
+    }
+}
+
+impl<K: PartialOrd + Copy, V: PartialEq + Clone> From<PersistentRangeMap<K, V>> for RangeMap<K, V> {
+    fn from(map: PersistentRangeMap<K, V>) -> Self {
+        Arc::try_unwrap(map.map).unwrap_or_else(|map| (*map).clone())
+    }
+}
+
+impl<K: PartialOrd + Copy, V: PartialEq + Clone> Deref for PersistentRangeMap<K, V> {
+    type Target = RangeMap<K, V>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.map
+    }
+}
+
+impl<K: PartialOrd + Copy, V: PartialEq + Clone> PersistentRangeMap<K, V> {
+    pub fn from(out_of_range_value: V) -> Self {
+        From::from(out_of_range_value)
+    }
+
+    pub fn set(&mut self, value: V, min: K, max: K) {
+        Arc::make_mut(&mut self.map).set(value, min, max);
+    }
+
+    // [min, max)
+    pub fn update<F: FnMut(&V) -> V>(&mut self, min: K, max: K, f: F) {
+        Arc::make_mut(&mut self.map).update(min, max, f);
+    }
+
+    pub fn shares_with(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.map, &other.map)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{FillBox, FillGrid, FillN, FillRect, HeightField};
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    pub fn test_send_sync() {
+        assert_send_sync::<PersistentRangeMap<f64, usize>>();
+        assert_send_sync::<FillRect>();
+        assert_send_sync::<FillGrid<usize>>();
+        assert_send_sync::<HeightField>();
+        assert_send_sync::<FillBox>();
+        assert_send_sync::<FillN<3>>();
+    }
+
+    #[test]
+    pub fn test_snapshot() {
+        let mut range = PersistentRangeMap::from(0);
+        range.set(1, 10.0, 20.0);
+
+        let snapshot = range.clone();
+        assert!(snapshot.shares_with(&range));
+
+        range.set(2, 12.0, 14.0);
+        assert!(!snapshot.shares_with(&range));
+        assert_eq!(snapshot.value(13.0), &1);
+        assert_eq!(range.value(13.0), &2);
+
+        range = snapshot.clone();
+        assert_eq!(range, snapshot);
+        assert_eq!(range.data(), vec![(1, 10.0, 20.0)]);
+    }
+
+    #[test]
+    pub fn test_nested_sharing() {
+        let mut inner = PersistentRangeMap::from(0);
+        inner.set(1, 0.0, 1.0);
+
+        let mut outer = PersistentRangeMap::from(None);
+        outer.set(Some(inner.clone()), 0.0, 10.0);
+        outer.set(Some(inner.clone()), 20.0, 30.0);
+
+        let snapshot = outer.clone();
+        let Some(mut column) = outer.value(25.0).clone() else { panic!() };
+        column.set(2, 0.0, 1.0);
+        outer.set(Some(column), 20.0, 30.0);
+
+        let Some(kept) = snapshot.value(25.0) else { panic!() };
+        assert!(kept.shares_with(&inner));
+        let Some(untouched) = outer.value(5.0) else { panic!() };
+        assert!(untouched.shares_with(&inner));
+        let Some(changed) = outer.value(25.0) else { panic!() };
+        assert_eq!(changed.value(0.5), &2);
+    }
+}
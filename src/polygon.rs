@@ -0,0 +1,189 @@
+pub(crate) type Edge = ((f64, f64), (f64, f64));
+
+pub(crate) fn ring_edges(ring: &[(f64, f64)]) -> Vec<Edge> {
+    let mut edges = Vec::with_capacity(ring.len());
+    for i in 0..ring.len() {
+        edges.push((ring[i], ring[(i + 1) % ring.len()]));
+    }
+    return edges;
+}
+
+fn y_at(edge: &Edge, x: f64) -> f64 {
+    let ((x0, y0), (x1, y1)) = *edge;
+    if x == x0 {
+        return y0;
+    }
+    if x == x1 {
+        return y1;
+    }
+    y0 + (y1 - y0) * (x - x0) / (x1 - x0)
+}
+
+// Cuts the strips between consecutive splits so that none is wider than
+// tolerance where an edge is slanted across it.
+pub(crate) fn strips(mut splits: Vec<f64>, edges: &[Edge], tolerance: f64) -> Vec<(f64, f64)> {
+    splits.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    splits.dedup();
+
+    let mut r = Vec::new();
+    for i in 1..splits.len() {
+        let (min_x, max_x) = (splits[i-1], splits[i]);
+        let slanted = edges.iter().any(|edge| {
+            let ((x0, y0), (x1, y1)) = *edge;
+            y0 != y1 && x0.min(x1) <= min_x && max_x <= x0.max(x1)
+        });
+        if slanted {
+            r.append(&mut split_strip(min_x, max_x, tolerance));
+        } else {
+            r.push((min_x, max_x));
+        }
+    }
+    return r;
+}
+
+// Cuts [min_x, max_x] into even strips no wider than tolerance
+pub(crate) fn split_strip(min_x: f64, max_x: f64, tolerance: f64) -> Vec<(f64, f64)> {
+    let pieces = if tolerance > 0.0 {
+        ((max_x - min_x) / tolerance).ceil().max(1.0) as usize
+    } else {
+        1
+    };
+    let mut r = Vec::with_capacity(pieces);
+    for piece in 0..pieces {
+        let local_min_x = min_x + (max_x - min_x) * piece as f64 / pieces as f64;
+        let local_max_x = if piece + 1 == pieces {
+            max_x
+        } else {
+            min_x + (max_x - min_x) * (piece + 1) as f64 / pieces as f64
+        };
+        r.push((local_min_x, local_max_x));
+    }
+    return r;
+}
+
+// Vertical chord of the disc at x
+pub(crate) fn circle_chord(center: (f64, f64), radius: f64, x: f64) -> Option<(f64, f64)> {
+    let dx = x - center.0;
+    if radius < dx.abs() {
+        return None;
+    }
+    let half = (radius * radius - dx * dx).max(0.0).sqrt();
+    Some((center.1 - half, center.1 + half))
+}
+
+// Vertical chord of a convex polygon at x
+pub(crate) fn convex_chord(points: &[(f64, f64)], x: f64) -> Option<(f64, f64)> {
+    let mut r: Option<(f64, f64)> = None;
+    let mut add = |y: f64| {
+        r = Some(match r {
+            Some((min_y, max_y)) => (min_y.min(y), max_y.max(y)),
+            None => (y, y),
+        });
+    };
+    for edge in ring_edges(points) {
+        let ((x0, y0), (x1, y1)) = edge;
+        if x0 == x1 {
+            if x0 == x {
+                add(y0);
+                add(y1);
+            }
+        } else if x0.min(x1) <= x && x <= x0.max(x1) {
+            add(y_at(&edge, x));
+        }
+    }
+    return r;
+}
+
+// Vertical chord at x of every point within radius of the segment
+pub(crate) fn capsule_chord(p0: (f64, f64), p1: (f64, f64), radius: f64, x: f64) -> Option<(f64, f64)> {
+    let (dx, dy) = (p1.0 - p0.0, p1.1 - p0.1);
+    let length = (dx * dx + dy * dy).sqrt();
+    let mut chords = vec![
+        circle_chord(p0, radius, x),
+        circle_chord(p1, radius, x),
+    ];
+    if length > 0.0 {
+        let (nx, ny) = (-dy / length * radius, dx / length * radius);
+        chords.push(convex_chord(&[
+            (p0.0 + nx, p0.1 + ny),
+            (p1.0 + nx, p1.1 + ny),
+            (p1.0 - nx, p1.1 - ny),
+            (p0.0 - nx, p0.1 - ny),
+        ], x));
+    }
+    chords.into_iter().flatten().reduce(|a, b| (a.0.min(b.0), a.1.max(b.1)))
+}
+
+// Range inside a convex shape for every x of [min_x, max_x]
+pub(crate) fn convex_inner<F: Fn(f64) -> Option<(f64, f64)>>(
+    chord: F, min_x: f64, max_x: f64,
+) -> Option<(f64, f64)> {
+    let (min_a, max_a) = chord(min_x)?;
+    let (min_b, max_b) = chord(max_x)?;
+    let (min_y, max_y) = (min_a.max(min_b), max_a.min(max_b));
+    if min_y < max_y { Some((min_y, max_y)) } else { None }
+}
+
+// y ranges inside the rings (even odd rule) over the strip [min_x, max_x],
+// which no vertex may fall strictly inside of. Inner ranges are inside for
+// every x of the strip, outer ranges cover every inside point of it.
+pub(crate) fn strip_intervals(edges: &[Edge], min_x: f64, max_x: f64, inner: bool) -> Vec<(f64, f64)> {
+    let mut crossing = Vec::new();
+    for edge in edges {
+        let ((x0, _), (x1, _)) = *edge;
+        if x0 == x1 || min_x < x0.min(x1) || x0.max(x1) < max_x {
+            continue;
+        }
+        crossing.push((y_at(edge, min_x), y_at(edge, max_x)));
+    }
+    crossing.sort_by(|a, b| (a.0 + a.1).partial_cmp(&(b.0 + b.1)).unwrap());
+
+    let mut r = Vec::new();
+    for pair in crossing.chunks_exact(2) {
+        let (low, high) = (pair[0], pair[1]);
+        let (min_y, max_y) = if inner {
+            (low.0.max(low.1), high.0.min(high.1))
+        } else {
+            (low.0.min(low.1), high.0.max(high.1))
+        };
+        if min_y < max_y {
+            r.push((min_y, max_y));
+        }
+    }
+    return r;
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_strips() {
+        let edges = ring_edges(&[(0.0, 0.0), (4.0, 0.0), (4.0, 2.0), (2.0, 4.0), (0.0, 4.0)]);
+        assert_eq!(
+            strips(vec![4.0, 0.0, 2.0, 4.0], &edges, 1.0),
+            vec![(0.0, 2.0), (2.0, 3.0), (3.0, 4.0)],
+        );
+    }
+
+    #[test]
+    pub fn test_strip_intervals() {
+        let edges = ring_edges(&[(0.0, 0.0), (4.0, 0.0), (4.0, 2.0), (2.0, 4.0), (0.0, 4.0)]);
+        assert_eq!(strip_intervals(&edges, 0.0, 2.0, true), vec![(0.0, 4.0)]);
+        assert_eq!(strip_intervals(&edges, 2.0, 3.0, true), vec![(0.0, 3.0)]);
+        assert_eq!(strip_intervals(&edges, 2.0, 3.0, false), vec![(0.0, 4.0)]);
+    }
+
+    #[test]
+    pub fn test_capsule_chord() {
+        let chord = |x| capsule_chord((0.0, 0.0), (4.0, 0.0), 1.0, x);
+        assert_eq!(chord(-2.0), None);
+        assert_eq!(chord(-1.0), Some((0.0, 0.0)));
+        assert_eq!(chord(2.0), Some((-1.0, 1.0)));
+        assert_eq!(chord(5.0), Some((0.0, 0.0)));
+
+        let chord = |x| capsule_chord((0.0, 0.0), (0.0, 4.0), 1.0, x);
+        assert_eq!(chord(0.0), Some((-1.0, 5.0)));
+        assert_eq!(chord(1.0), Some((0.0, 4.0)));
+    }
+}
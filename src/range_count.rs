@@ -0,0 +1,119 @@
+use super::range_map::RangeMap;
+
+// Counts how many times each point has been covered.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RangeCount<K: PartialOrd + Copy> {
+    map: RangeMap<K, usize>,
+}
+
+impl<K: PartialOrd + Copy> Default for RangeCount<K> {
+    fn default() -> Self {
+        Self {
+            map: RangeMap::from(0),
+        }
+    }
+}
+
+impl<K: PartialOrd + Copy> From<RangeCount<K>> for RangeMap<K, usize> {
+    fn from(range_count: RangeCount<K>) -> Self {
+        range_count.map
+    }
+}
+
+impl<K: PartialOrd + Copy> RangeCount<K> {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    // [min, max), depths never drop below zero
+    pub fn add(&mut self, delta: isize, min: K, max: K) {
+        self.map.update(min, max, |depth| {
+            if delta < 0 {
+                depth.saturating_sub(delta.unsigned_abs())
+            } else {
+                depth + delta as usize
+            }
+        });
+    }
+
+    pub fn depth_at(&self, k: K) -> usize {
+        *self.map.value(k)
+    }
+
+    // [min, max)
+    pub fn max_depth(&self, min: K, max: K) -> usize {
+        self.map.data_range_clipped(min, max)
+            .into_iter()
+            .map(|(depth, _, _)| depth)
+            .max()
+            .unwrap_or(0)
+    }
+
+    pub fn regions_with_depth<F: Fn(usize) -> bool>(&self, pred: F) -> Vec<(usize, K, K)> {
+        self.map.data()
+            .into_iter()
+            .filter(|(depth, min, max)| min < max && pred(*depth))
+            .collect()
+    }
+
+    pub fn data(&self) -> Vec<(usize, K, K)> {
+        self.map.data()
+    }
+
+    pub fn map(&self) -> &RangeMap<K, usize> {
+        &self.map
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_depth() {
+        let mut count = RangeCount::new();
+        count.add(1, 0.0, 10.0);
+        count.add(1, 5.0, 15.0);
+        assert_eq!(count.depth_at(-1.0), 0);
+        assert_eq!(count.depth_at(2.0), 1);
+        assert_eq!(count.depth_at(5.0), 2);
+        assert_eq!(count.depth_at(12.0), 1);
+        assert_eq!(count.depth_at(15.0), 0);
+        assert_eq!(count.data(), vec![
+            (1, 0.0, 5.0),
+            (2, 5.0, 10.0),
+            (1, 10.0, 15.0),
+        ]);
+    }
+
+    #[test]
+    pub fn test_max_depth() {
+        let mut count = RangeCount::new();
+        count.add(1, 0.0, 10.0);
+        count.add(2, 4.0, 6.0);
+        assert_eq!(count.max_depth(0.0, 4.0), 1);
+        assert_eq!(count.max_depth(3.0, 5.0), 3);
+        assert_eq!(count.max_depth(20.0, 30.0), 0);
+    }
+
+    #[test]
+    pub fn test_regions_with_depth() {
+        let mut count = RangeCount::new();
+        count.add(1, 0.0, 10.0);
+        count.add(1, 2.0, 3.0);
+        count.add(1, 7.0, 9.0);
+        assert_eq!(count.regions_with_depth(|depth| depth > 1), vec![
+            (2, 2.0, 3.0),
+            (2, 7.0, 9.0),
+        ]);
+    }
+
+    #[test]
+    pub fn test_remove() {
+        let mut count = RangeCount::new();
+        count.add(2, 0.0, 10.0);
+        count.add(-1, 0.0, 5.0);
+        count.add(-3, 5.0, 10.0);
+        assert_eq!(count.data(), vec![(1, 0.0, 5.0)]);
+    }
+}
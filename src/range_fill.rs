@@ -1,57 +1,82 @@
-use algorithms;
+use std::fmt;
 
+use super::journal::{self, Journaled, RangePatch};
+use super::range_map::RangeMap;
+
+// Filled ranges map to true, open ranges to false. Everything outside of
+// the open range given on creation starts out filled.
 #[derive(Debug, Clone, PartialEq)]
 pub struct RangeFill {
-    fill: Vec<f64>
+    map: RangeMap<f64, bool>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UnboundedOpenRange;
+
+impl fmt::Display for UnboundedOpenRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "range map is open outside of its ranges")
+    }
 }
 
+impl std::error::Error for UnboundedOpenRange {}
+
 impl From<(f64, f64)> for RangeFill {
     fn from(open_range: (f64, f64)) -> Self {
+        let mut map = RangeMap::from(true);
+        map.set(false, open_range.0, open_range.1);
         Self {
-            fill: vec![
-                open_range.0,
-                open_range.1,
-            ]
+            map
         }
     }
 }
 
-impl RangeFill {
-    pub fn from(min: f64, max: f64) -> Self {
-        From::from((min, max))
+impl From<RangeFill> for RangeMap<f64, bool> {
+    fn from(range_fill: RangeFill) -> Self {
+        range_fill.map
     }
-    pub fn fill(&mut self, min: f64, max: f64) {
-        let mut seen_before_min = algorithms::seen_before_or_equal(&self.fill, min);
-        let mut seen_before_max = algorithms::seen_before(&self.fill, max);
-
-        if seen_before_min == seen_before_max {
-            if seen_before_min % 2 == 1 {
-                self.fill.insert(seen_before_min, max);
-                self.fill.insert(seen_before_min, min);
-            }
-            return;
-        }
+}
 
-        // is fill
-        if seen_before_min % 2 == 1 {
-            self.fill[seen_before_min] = min;
-            seen_before_min += 1;
-        }
+impl TryFrom<RangeMap<f64, bool>> for RangeFill {
+    type Error = UnboundedOpenRange;
 
-        if seen_before_max % 2 == 1 {
-            seen_before_max -= 1;
-            self.fill[seen_before_max] = max;
+    fn try_from(map: RangeMap<f64, bool>) -> Result<Self, Self::Error> {
+        if !*map.default_value() {
+            return Err(UnboundedOpenRange);
         }
+        Ok(Self {
+            map
+        })
+    }
+}
+
+impl Journaled for RangeFill {
+    type Patch = RangePatch<f64, bool>;
 
-        self.fill.drain(seen_before_min..seen_before_max);
+    fn apply_patch(&mut self, patch: Self::Patch) -> Self::Patch {
+        journal::apply_range_patch(&mut self.map, patch)
+    }
+}
+
+impl RangeFill {
+    pub fn from(min: f64, max: f64) -> Self {
+        From::from((min, max))
+    }
+    pub fn fill(&mut self, min: f64, max: f64) {
+        self.map.set(true, min, max);
     }
 
     // [min, max)
     pub fn contains_unfilled(&self, min: f64, max: f64) -> bool {
-        let seen_before_min = algorithms::seen_before_or_equal(&self.fill, min);
-        let seen_before_max = algorithms::seen_before(&self.fill, max);
+        self.map.values(min, max).into_iter().any(|is_fill| !*is_fill)
+    }
 
-        !(seen_before_min % 2 == 0 && seen_before_min == seen_before_max)
+    pub fn get_ranges(&self) -> &Vec<f64> {
+        self.map.get_ranges()
+    }
+
+    pub fn map(&self) -> &RangeMap<f64, bool> {
+        &self.map
     }
 }
 
@@ -79,14 +104,14 @@ mod test {
 
         range.fill(13.0, 14.0);
         assert_eq!(range.contains_unfilled(13.0, 14.0), false);
-        assert_eq!(range.fill, vec![10.0, 11.0, 12.0, 13.0, 14.0, 20.0]);
+        assert_eq!(range.get_ranges(), &vec![10.0, 11.0, 12.0, 13.0, 14.0, 20.0]);
 
         range.fill(15.0, 16.0);
         assert_eq!(range.contains_unfilled(15.0, 16.0), false);
 
         range.fill(17.0, 18.0);
         assert_eq!(range.contains_unfilled(17.0, 18.0), false);
-        assert_eq!(range.fill, vec![10.0, 11.0, 12.0, 13.0, 14.0, 15.0, 16.0, 17.0, 18.0, 20.0]);
+        assert_eq!(range.get_ranges(), &vec![10.0, 11.0, 12.0, 13.0, 14.0, 15.0, 16.0, 17.0, 18.0, 20.0]);
     }
 
     #[test]
@@ -97,31 +122,71 @@ mod test {
         range.fill(13.0, 14.0);
         range.fill(15.0, 16.0);
         range.fill(17.0, 18.0);
-        assert_eq!(range.fill, vec![10.0, 11.0, 12.0, 13.0, 14.0, 15.0, 16.0, 17.0, 18.0, 20.0]);
+        assert_eq!(range.get_ranges(), &vec![10.0, 11.0, 12.0, 13.0, 14.0, 15.0, 16.0, 17.0, 18.0, 20.0]);
 
         // Fill [Open, Close]
         range.fill(10.5, 15.5);
-        assert_eq!(range.fill, vec![10.0, 10.5, 16.0, 17.0, 18.0, 20.0]);
+        assert_eq!(range.get_ranges(), &vec![10.0, 10.5, 16.0, 17.0, 18.0, 20.0]);
 
         // Fill [Close, Open]
         range.fill(15.0, 16.5);
-        assert_eq!(range.fill, vec![10.0, 10.5, 16.5, 17.0, 18.0, 20.0]);
+        assert_eq!(range.get_ranges(), &vec![10.0, 10.5, 16.5, 17.0, 18.0, 20.0]);
 
         // Fill [Open, Open]
         range.fill(16.9, 18.5);
-        assert_eq!(range.fill, vec![10.0, 10.5, 16.5, 16.9, 18.5, 20.0]);
+        assert_eq!(range.get_ranges(), &vec![10.0, 10.5, 16.5, 16.9, 18.5, 20.0]);
 
         // Fill [Close, Close]
         range.fill(12.0, 18.0);
-        assert_eq!(range.fill, vec![10.0, 10.5, 18.5, 20.0]);
+        assert_eq!(range.get_ranges(), &vec![10.0, 10.5, 18.5, 20.0]);
 
         // Fill [Before, Close]
         range.fill(0.0, 18.0);
-        assert_eq!(range.fill, vec![18.5, 20.0]);
+        assert_eq!(range.get_ranges(), &vec![18.5, 20.0]);
 
         // Fill [Before, After]
         range.fill(0.0, 25.0);
-        assert_eq!(range.fill, vec![]);
+        assert_eq!(range.get_ranges(), &vec![]);
     }
-}
 
+    #[test]
+    pub fn test_into_range_map() {
+        let mut range = RangeFill::from(10.0, 20.0);
+        range.fill(11.0, 12.0);
+        range.fill(13.0, 14.0);
+
+        let map: RangeMap<f64, bool> = range.clone().into();
+        assert_eq!(map.get_ranges(), &vec![10.0, 11.0, 12.0, 13.0, 14.0, 20.0]);
+        assert_eq!(map.data(), vec![
+            (false, 10.0, 11.0),
+            (true, 11.0, 12.0),
+            (false, 12.0, 13.0),
+            (true, 13.0, 14.0),
+            (false, 14.0, 20.0),
+        ]);
+        assert_eq!(RangeFill::try_from(map), Ok(range));
+    }
+
+    #[test]
+    pub fn test_try_from_range_map() {
+        let mut map = RangeMap::from(true);
+        map.set(false, 0.0, 5.0);
+        map.set(true, 1.0, 2.0);
+        let range = RangeFill::try_from(map).unwrap();
+        assert_eq!(range.contains_unfilled(1.0, 2.0), false);
+        assert_eq!(range.contains_unfilled(2.0, 3.0), true);
+
+        let mut map = RangeMap::from(false);
+        map.set(true, 0.0, 5.0);
+        assert_eq!(RangeFill::try_from(map), Err(UnboundedOpenRange));
+    }
+
+    #[test]
+    pub fn test_fill_past_end() {
+        let mut range = RangeFill::from(10.0, 20.0);
+        range.fill(25.0, 30.0);
+        range.fill(20.0, 22.0);
+        assert_eq!(range.contains_unfilled(10.0, 20.0), true);
+        assert_eq!(range.contains_unfilled(21.0, 30.0), false);
+    }
+}
@@ -32,6 +32,11 @@ impl<K: PartialOrd + Copy, V: PartialEq + Clone> RangeMap<K, V> {
         &self.ranges
     }
 
+    // values[i] is the value over [ranges[i], ranges[i+1])
+    pub fn get_values(&self) -> &Vec<V> {
+        &self.values
+    }
+
     // [min, max)
     pub fn data_range(&self, min: K, max: K) -> Vec<(V, K, K)> {
         let mut seen_before_min = algorithms::seen_before_or_equal(&self.ranges, min);
@@ -72,11 +77,59 @@ impl<K: PartialOrd + Copy, V: PartialEq + Clone> RangeMap<K, V> {
         return r;
     }
 
+    // [min, max), cut to the query with the gaps filled by the default value
+    pub fn data_range_clipped(&self, min: K, max: K) -> Vec<(V, K, K)> {
+        let mut r = Vec::new();
+        if max <= min {
+            return r;
+        }
+
+        let mut cursor = min;
+        let start = algorithms::seen_before_or_equal_address(&self.ranges, &min);
+        for i in start.saturating_sub(1)..self.values.len() {
+            let local_min = self.ranges[i];
+            let local_max = self.ranges[i+1];
+            if max <= local_min {
+                break;
+            }
+            if local_max <= cursor {
+                continue;
+            }
+            if cursor < local_min {
+                r.push((self.default_value.clone(), cursor, local_min));
+                cursor = local_min;
+            }
+            let end = if local_max < max { local_max } else { max };
+            r.push((self.values[i].clone(), cursor, end));
+            cursor = end;
+        }
+        if cursor < max {
+            r.push((self.default_value.clone(), cursor, max));
+        }
+
+        return r;
+    }
+
     pub fn default_value(&self) -> &V {
         &self.default_value
     }
 
     pub fn set(&mut self, value: V, min: K, max: K) {
+        if self.ranges.len() == 0 && value == self.default_value {
+            return;
+        }
+
+        // past the end, leaving a gap of the default value
+        if self.ranges.len() > 0 && self.ranges[self.ranges.len()-1] < min {
+            if value != self.default_value {
+                self.values.push(self.default_value.clone());
+                self.values.push(value);
+                self.ranges.push(min);
+                self.ranges.push(max);
+            }
+            return;
+        }
+
         let mut min = min;
         let mut max = max;
 
@@ -136,6 +189,16 @@ impl<K: PartialOrd + Copy, V: PartialEq + Clone> RangeMap<K, V> {
         }
     }
 
+    // [min, max)
+    pub fn update<F: FnMut(&V) -> V>(&mut self, min: K, max: K, mut f: F) {
+        for (value, local_min, local_max) in self.data_range_clipped(min, max) {
+            let new_value = f(&value);
+            if new_value != value {
+                self.set(new_value, local_min, local_max);
+            }
+        }
+    }
+
     pub fn value(&self, value: K) -> &V {
         let seen_before = algorithms::seen_before_or_equal(&self.ranges, value);
         if seen_before == 0 || seen_before > self.values.len() {
@@ -286,4 +349,69 @@ mod test {
         assert_eq!(range.ranges, vec![]);
         assert_eq!(range.values, vec![]);
     }
+
+    #[test]
+    pub fn test_set_past_end() {
+        let mut range = RangeMap::from(0);
+        range.set(1, 0.0, 10.0);
+        range.set(2, 20.0, 30.0);
+        assert_eq!(range.ranges, vec![0.0, 10.0, 20.0, 30.0]);
+        assert_eq!(range.values, vec![1, 0, 2]);
+
+        range.set(0, 40.0, 50.0);
+        assert_eq!(range.ranges, vec![0.0, 10.0, 20.0, 30.0]);
+        assert_eq!(range.values, vec![1, 0, 2]);
+        assert_eq!(range.value(25.0), &2);
+        assert_eq!(range.value(35.0), &0);
+    }
+
+    #[test]
+    pub fn test_set_default_on_empty() {
+        let mut range = RangeMap::from(0);
+        range.set(0, 0.0, 10.0);
+        assert_eq!(range.ranges, vec![]);
+        assert_eq!(range.values, vec![]);
+    }
+
+    #[test]
+    pub fn test_set_touching_end() {
+        let mut range = RangeMap::from(0);
+        range.set(1, 0.0, 10.0);
+        range.set(2, 10.0, 20.0);
+        assert_eq!(range.value(5.0), &1);
+        assert_eq!(range.value(15.0), &2);
+        assert_eq!(range.value(25.0), &0);
+    }
+
+    #[test]
+    pub fn test_data_range_clipped() {
+        let mut range = RangeMap::from(0);
+        range.set(1, 10.0, 20.0);
+        range.set(2, 12.0, 14.0);
+        assert_eq!(range.data_range_clipped(5.0, 13.0), vec![
+            (0, 5.0, 10.0),
+            (1, 10.0, 12.0),
+            (2, 12.0, 13.0),
+        ]);
+        assert_eq!(range.data_range_clipped(13.0, 25.0), vec![
+            (2, 13.0, 14.0),
+            (1, 14.0, 20.0),
+            (0, 20.0, 25.0),
+        ]);
+        assert_eq!(range.data_range_clipped(0.0, 5.0), vec![(0, 0.0, 5.0)]);
+        assert_eq!(range.data_range_clipped(5.0, 5.0), vec![]);
+    }
+
+    #[test]
+    pub fn test_update() {
+        let mut range = RangeMap::from(0);
+        range.set(1, 10.0, 20.0);
+        range.update(5.0, 15.0, |value| value + 1);
+        assert_eq!(range.ranges, vec![5.0, 10.0, 15.0, 20.0]);
+        assert_eq!(range.values, vec![1, 2, 1]);
+
+        range.update(0.0, 30.0, |_| 0);
+        assert_eq!(range.ranges, vec![]);
+        assert_eq!(range.values, vec![]);
+    }
 }
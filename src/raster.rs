@@ -0,0 +1,171 @@
+use super::fill_grid::Column;
+use super::fill_rect::{FillRect, FillState};
+
+impl FillRect {
+    // px_w by px_h pixels over the window, top row first. Each pixel is the
+    // filled fraction of its area scaled to 0..=255, where anything that is
+    // not open counts as filled.
+    pub fn rasterize(
+        &self,
+        min_x: f64, min_y: f64,
+        max_x: f64, max_y: f64,
+        px_w: usize, px_h: usize,
+    ) -> Vec<u8> {
+        if px_w == 0 || px_h == 0 || max_x <= min_x || max_y <= min_y {
+            return vec![255; px_w * px_h];
+        }
+        let pixel_w = (max_x - min_x) / px_w as f64;
+        let pixel_h = (max_y - min_y) / px_h as f64;
+        let pixel_x = |i: usize| if i == px_w { max_x } else { min_x + pixel_w * i as f64 };
+        let pixel_y = |j: usize| if j == px_h { max_y } else { min_y + pixel_h * j as f64 };
+        let first_pixel = |v: f64, size: f64, count: usize| (v / size).floor().max(0.0).min(count as f64) as usize;
+        let last_pixel = |v: f64, size: f64, count: usize| (v / size).ceil().max(0.0).min(count as f64) as usize;
+
+        // open area of each pixel, bottom row first
+        let mut open = vec![0.0; px_w * px_h];
+        for (column_min_x, column_max_x, cells) in self.columns() {
+            let (x0, x1) = (column_min_x.max(min_x), column_max_x.min(max_x));
+            if x1 <= x0 {
+                continue;
+            }
+            for (state, cell_min_y, cell_max_y) in cells {
+                let (y0, y1) = (cell_min_y.max(min_y), cell_max_y.min(max_y));
+                if state == FillState::Filled || y1 <= y0 {
+                    continue;
+                }
+                for i in first_pixel(x0 - min_x, pixel_w, px_w)..last_pixel(x1 - min_x, pixel_w, px_w) {
+                    let w = x1.min(pixel_x(i + 1)) - x0.max(pixel_x(i));
+                    if w <= 0.0 {
+                        continue;
+                    }
+                    for j in first_pixel(y0 - min_y, pixel_h, px_h)..last_pixel(y1 - min_y, pixel_h, px_h) {
+                        let h = y1.min(pixel_y(j + 1)) - y0.max(pixel_y(j));
+                        if h > 0.0 {
+                            open[j * px_w + i] += w * h;
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut r = Vec::with_capacity(px_w * px_h);
+        for row in 0..px_h {
+            let j = px_h - 1 - row;
+            for i in 0..px_w {
+                let area = (pixel_x(i + 1) - pixel_x(i)) * (pixel_y(j + 1) - pixel_y(j));
+                let filled = (1.0 - open[j * px_w + i] / area).clamp(0.0, 1.0);
+                r.push((filled * 255.0).round() as u8);
+            }
+        }
+        return r;
+    }
+
+    // Work region covering every pixel of bits, with true pixels filled.
+    // bits[row][column] has its top row first and origin is the lower left
+    // corner of the bottom row. Runs of equal pixels become one strip and
+    // neighbouring columns with the same strips become one column.
+    pub fn from_bitmap(bits: &[Vec<bool>], pixel_size: f64, origin: (f64, f64)) -> Self {
+        let width = bits.iter().map(|row| row.len()).max().unwrap_or(0);
+        let rows = bits.len();
+        let x_at = |i: usize| origin.0 + pixel_size * i as f64;
+        let y_at = |j: usize| origin.1 + pixel_size * j as f64;
+
+        let mut columns: Vec<Column<FillState>> = Vec::new();
+        for i in 0..width {
+            let mut cells: Vec<(FillState, f64, f64)> = Vec::new();
+            let mut run: Option<(bool, usize)> = None;
+            for j in 0..=rows {
+                let pixel = if j < rows { bits[rows - 1 - j].get(i).copied() } else { None };
+                if run.map(|(value, _)| Some(value)) == Some(pixel) {
+                    continue;
+                }
+                if let Some((value, start)) = run {
+                    let state = if value { FillState::Filled } else { FillState::Open };
+                    cells.push((state, y_at(start), y_at(j)));
+                }
+                run = pixel.map(|value| (value, j));
+            }
+
+            if let Some(last) = columns.last_mut() {
+                if last.2 == cells {
+                    last.1 = x_at(i + 1);
+                    continue;
+                }
+            }
+            columns.push((x_at(i), x_at(i + 1), cells));
+        }
+
+        FillRect::from_columns(columns)
+    }
+}
+
+fn netpbm(magic: &str, width: usize, height: usize, pixels: &[u8]) -> Vec<u8> {
+    let mut r = format!("{}\n{} {}\n255\n", magic, width, height).into_bytes();
+    r.extend_from_slice(pixels);
+    return r;
+}
+
+// Binary PGM (P5) of width by height grey pixels, top row first
+pub fn write_pgm(width: usize, height: usize, pixels: &[u8]) -> Vec<u8> {
+    assert_eq!(pixels.len(), width * height);
+    netpbm("P5", width, height, pixels)
+}
+
+// Binary PPM (P6) of width by height rgb pixels, top row first
+pub fn write_ppm(width: usize, height: usize, pixels: &[(u8, u8, u8)]) -> Vec<u8> {
+    assert_eq!(pixels.len(), width * height);
+    let rgb: Vec<u8> = pixels.iter().flat_map(|(r, g, b)| [*r, *g, *b]).collect();
+    netpbm("P6", width, height, &rgb)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_rasterize() {
+        let mut fill_rect = FillRect::from(0.0, 0.0, 4.0, 2.0);
+        fill_rect.fill_rect(0.0, 0.0, 1.5, 1.0);
+        assert_eq!(fill_rect.rasterize(0.0, 0.0, 4.0, 2.0, 4, 2), vec![
+            0, 0, 0, 0,
+            255, 128, 0, 0,
+        ]);
+        // outside the work region counts as filled
+        assert_eq!(fill_rect.rasterize(3.0, 1.0, 5.0, 2.0, 2, 1), vec![0, 255]);
+    }
+
+    #[test]
+    pub fn test_from_bitmap() {
+        let fill_rect = FillRect::from_bitmap(&[
+            vec![false, false, true],
+            vec![true, true, false],
+        ], 2.0, (10.0, 20.0));
+        assert_eq!(fill_rect.bounds(), Some((10.0, 20.0, 16.0, 24.0)));
+        assert_eq!(fill_rect.get_open_rects(), vec![
+            (10.0, 22.0, 14.0, 24.0),
+            (14.0, 20.0, 16.0, 22.0),
+        ]);
+        assert_eq!(fill_rect.rasterize(10.0, 20.0, 16.0, 24.0, 3, 2), vec![
+            0, 0, 255,
+            255, 255, 0,
+        ]);
+    }
+
+    #[test]
+    pub fn test_from_bitmap_ragged() {
+        let fill_rect = FillRect::from_bitmap(&[
+            vec![false],
+            vec![false, false],
+        ], 1.0, (0.0, 0.0));
+        assert_eq!(fill_rect.get_open_rects(), vec![
+            (0.0, 0.0, 1.0, 2.0),
+            (1.0, 0.0, 2.0, 1.0),
+        ]);
+    }
+
+    #[test]
+    pub fn test_netpbm() {
+        assert_eq!(write_pgm(2, 1, &[0, 255]), b"P5\n2 1\n255\n\x00\xff".to_vec());
+        assert_eq!(write_ppm(1, 1, &[(1, 2, 3)]), b"P6\n1 1\n255\n\x01\x02\x03".to_vec());
+    }
+}
@@ -0,0 +1,178 @@
+use super::fill_rect::{FillRect, FillState};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hit {
+    pub x: f64,
+    pub y: f64,
+    pub distance: f64,
+    // unit normal of the edge hit, facing back along the ray
+    pub normal: (f64, f64),
+    // whether the ray goes from open into filled, rather than the other way
+    pub enters_filled: bool,
+}
+
+impl FillRect {
+    fn is_fill_at(&self, x: f64, y: f64) -> bool {
+        self.value_at(x, y) != Some(&FillState::Open)
+    }
+
+    // Boundaries of the column around x that the ray crosses after t_min and
+    // by t_max, as (t, normal)
+    fn strip_crossings(
+        &self,
+        origin: (f64, f64), direction: (f64, f64),
+        t_min: f64, t_max: f64,
+    ) -> Vec<(f64, (f64, f64))> {
+        let mut r = Vec::new();
+        if direction.1 == 0.0 {
+            return r;
+        }
+        let x = origin.0 + direction.0 * (t_min + t_max) / 2.0;
+        let Some(column) = self.map.value(x) else { return r };
+        let normal = (0.0, -direction.1.signum());
+        for y in column.get_ranges() {
+            let t = (y - origin.1) / direction.1;
+            if t_min < t && t <= t_max {
+                r.push((t, normal));
+            }
+        }
+        r.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        return r;
+    }
+
+    // First place along the ray, within max_dist of the origin, where it
+    // crosses from open into filled or back. Steps from one column or strip
+    // boundary to the next and looks at the cell between each pair, so
+    // anything that is not open counts as filled like in is_fill_point.
+    pub fn raycast(&self, origin: (f64, f64), direction: (f64, f64), max_dist: f64) -> Option<Hit> {
+        let length = direction.0.hypot(direction.1);
+        if !(length > 0.0 && max_dist > 0.0) {
+            return None;
+        }
+        let direction = (direction.0 / length, direction.1 / length);
+        let at = |t: f64| (origin.0 + direction.0 * t, origin.1 + direction.1 * t);
+
+        // column boundaries the ray crosses, in order
+        let mut column_crossings: Vec<f64> = Vec::new();
+        if direction.0 != 0.0 {
+            for x in self.map.get_ranges() {
+                let t = (x - origin.0) / direction.0;
+                if 0.0 < t && t <= max_dist {
+                    column_crossings.push(t);
+                }
+            }
+            if direction.0 < 0.0 {
+                column_crossings.reverse();
+            }
+        }
+        column_crossings.push(max_dist);
+        let column_normal = (-direction.0.signum(), 0.0);
+
+        let mut was_fill: Option<bool> = None;
+        let mut last_t = 0.0;
+        let mut last_normal = (0.0, 0.0);
+        let mut column_start = 0.0;
+        for (i, column_end) in column_crossings.iter().enumerate() {
+            let mut crossings = self.strip_crossings(origin, direction, column_start, *column_end);
+            // the end of the ray only closes the last cell
+            let end_normal = if i + 1 < column_crossings.len() { column_normal } else { (0.0, 0.0) };
+            crossings.push((*column_end, end_normal));
+            column_start = *column_end;
+
+            for (t, normal) in crossings {
+                if t == last_t {
+                    last_normal = (last_normal.0 + normal.0, last_normal.1 + normal.1);
+                    continue;
+                }
+                let (x, y) = at((last_t + t) / 2.0);
+                let is_fill = self.is_fill_at(x, y);
+                if was_fill.is_some_and(|was_fill| was_fill != is_fill) {
+                    let (x, y) = at(last_t);
+                    let normal_length = last_normal.0.hypot(last_normal.1);
+                    return Some(Hit {
+                        x,
+                        y,
+                        distance: last_t,
+                        normal: (last_normal.0 / normal_length, last_normal.1 / normal_length),
+                        enters_filled: is_fill,
+                    });
+                }
+                was_fill = Some(is_fill);
+                last_t = t;
+                last_normal = normal;
+            }
+        }
+
+        return None;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_raycast_into_filled() {
+        let mut fill_rect = FillRect::from(0.0, 0.0, 10.0, 10.0);
+        fill_rect.fill_rect(6.0, 4.0, 8.0, 6.0);
+        assert_eq!(fill_rect.raycast((1.0, 5.0), (1.0, 0.0), 20.0), Some(Hit {
+            x: 6.0,
+            y: 5.0,
+            distance: 5.0,
+            normal: (-1.0, 0.0),
+            enters_filled: true,
+        }));
+        assert_eq!(fill_rect.raycast((7.0, 1.0), (0.0, 2.0), 20.0), Some(Hit {
+            x: 7.0,
+            y: 4.0,
+            distance: 3.0,
+            normal: (0.0, -1.0),
+            enters_filled: true,
+        }));
+        // too short to get there
+        assert_eq!(fill_rect.raycast((1.0, 5.0), (1.0, 0.0), 4.0), None);
+        assert_eq!(fill_rect.raycast((1.0, 5.0), (0.0, 0.0), 4.0), None);
+    }
+
+    #[test]
+    pub fn test_raycast_out_of_filled() {
+        let mut fill_rect = FillRect::from(0.0, 0.0, 10.0, 10.0);
+        fill_rect.fill_rect(6.0, 4.0, 8.0, 6.0);
+        assert_eq!(fill_rect.raycast((7.0, 5.0), (-1.0, 0.0), 20.0), Some(Hit {
+            x: 6.0,
+            y: 5.0,
+            distance: 1.0,
+            normal: (1.0, 0.0),
+            enters_filled: false,
+        }));
+        // from outside of the work region into it
+        assert_eq!(fill_rect.raycast((5.0, -3.0), (0.0, 1.0), 20.0), Some(Hit {
+            x: 5.0,
+            y: 0.0,
+            distance: 3.0,
+            normal: (0.0, -1.0),
+            enters_filled: false,
+        }));
+    }
+
+    #[test]
+    pub fn test_raycast_leaves_region() {
+        let fill_rect = FillRect::from(0.0, 0.0, 10.0, 10.0);
+        let hit = fill_rect.raycast((5.0, 5.0), (1.0, 1.0), 20.0).unwrap();
+        assert!((hit.x - 10.0).abs() < 1e-9 && (hit.y - 10.0).abs() < 1e-9);
+        assert!((hit.distance - 50.0_f64.sqrt()).abs() < 1e-9);
+        assert!(hit.enters_filled);
+        // corner hit faces back along both axes
+        assert!((hit.normal.0 + 0.5_f64.sqrt()).abs() < 1e-9 && (hit.normal.1 + 0.5_f64.sqrt()).abs() < 1e-9);
+    }
+
+    #[test]
+    pub fn test_raycast_skips_same_state_boundaries() {
+        let mut fill_rect = FillRect::from(0.0, 0.0, 10.0, 10.0);
+        // splits the columns without anything filled on the ray's row
+        fill_rect.fill_rect(2.0, 8.0, 3.0, 9.0);
+        fill_rect.fill_rect(4.0, 0.0, 5.0, 1.0);
+        let hit = fill_rect.raycast((0.5, 5.0), (1.0, 0.0), 20.0).unwrap();
+        assert_eq!((hit.x, hit.y, hit.normal), (10.0, 5.0, (-1.0, 0.0)));
+    }
+}
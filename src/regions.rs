@@ -0,0 +1,162 @@
+use super::fill_rect::FillRect;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Connectivity {
+    // rects sharing part of an edge
+    Four,
+    // rects sharing part of an edge or only a corner
+    Eight,
+}
+
+// One connected pocket of open area
+#[derive(Debug, Clone, PartialEq)]
+pub struct Region {
+    // (min_x, min_y, max_x, max_y)
+    pub bounds: (f64, f64, f64, f64),
+    pub area: f64,
+    // length of the boundary, holes included
+    pub perimeter: f64,
+    // the open rects of the region, as get_open_rects returns them
+    pub rects: Vec<(f64, f64, f64, f64)>,
+}
+
+fn find(parents: &mut [usize], i: usize) -> usize {
+    let mut root = i;
+    while parents[root] != root {
+        root = parents[root];
+    }
+    let mut i = i;
+    while parents[i] != root {
+        let next = parents[i];
+        parents[i] = root;
+        i = next;
+    }
+    return root;
+}
+
+impl FillRect {
+    // Open rects grouped into connected regions, in the order their first
+    // rect appears in get_open_rects.
+    pub fn open_regions(&self, connectivity: Connectivity) -> Vec<Region> {
+        let rects = self.get_open_rects();
+
+        // [start, end) of the rects of each column
+        let mut columns: Vec<(usize, usize)> = Vec::new();
+        for i in 0..rects.len() {
+            match columns.last_mut() {
+                Some(column) if rects[column.0].0 == rects[i].0 => column.1 = i + 1,
+                _ => columns.push((i, i + 1)),
+            }
+        }
+
+        let mut parents: Vec<usize> = (0..rects.len()).collect();
+        // (rect, length) of every edge shared by two rects
+        let mut shared: Vec<(usize, f64)> = Vec::new();
+        for pair in columns.windows(2) {
+            let (left, right) = (pair[0], pair[1]);
+            if rects[left.0].2 != rects[right.0].0 {
+                continue;
+            }
+            for a in left.0..left.1 {
+                let start = right.0 + rects[right.0..right.1].partition_point(|b| b.3 < rects[a].1);
+                for b in start..right.1 {
+                    if rects[a].3 < rects[b].1 {
+                        break;
+                    }
+                    let overlap = rects[a].3.min(rects[b].3) - rects[a].1.max(rects[b].1);
+                    if overlap > 0.0 || connectivity == Connectivity::Eight {
+                        let (root_a, root_b) = (find(&mut parents, a), find(&mut parents, b));
+                        parents[root_b] = root_a;
+                    }
+                    if overlap > 0.0 {
+                        shared.push((a, overlap));
+                    }
+                }
+            }
+        }
+
+        let mut r: Vec<Region> = Vec::new();
+        // index into r of each root
+        let mut region_of: Vec<Option<usize>> = vec![None; rects.len()];
+        for (i, &rect) in rects.iter().enumerate() {
+            let root = find(&mut parents, i);
+            let index = match region_of[root] {
+                Some(index) => index,
+                None => {
+                    region_of[root] = Some(r.len());
+                    r.push(Region {
+                        bounds: rect,
+                        area: 0.0,
+                        perimeter: 0.0,
+                        rects: Vec::new(),
+                    });
+                    r.len() - 1
+                },
+            };
+            let (min_x, min_y, max_x, max_y) = rect;
+            let region = &mut r[index];
+            region.bounds = (
+                region.bounds.0.min(min_x), region.bounds.1.min(min_y),
+                region.bounds.2.max(max_x), region.bounds.3.max(max_y),
+            );
+            region.area += (max_x - min_x) * (max_y - min_y);
+            region.perimeter += 2.0 * ((max_x - min_x) + (max_y - min_y));
+            region.rects.push(rect);
+        }
+
+        // shared edges are inside a region rather than on its boundary
+        for (a, overlap) in shared {
+            let index = region_of[find(&mut parents, a)].unwrap();
+            r[index].perimeter -= 2.0 * overlap;
+        }
+
+        return r;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_open_regions() {
+        let mut fill_rect = FillRect::from(0.0, 0.0, 10.0, 10.0);
+        fill_rect.fill_rect(4.0, 0.0, 6.0, 10.0);
+        fill_rect.fill_rect(6.0, 4.0, 10.0, 6.0);
+        let regions = fill_rect.open_regions(Connectivity::Four);
+        assert_eq!(regions.len(), 3);
+        assert_eq!(regions[0], Region {
+            bounds: (0.0, 0.0, 4.0, 10.0),
+            area: 40.0,
+            perimeter: 28.0,
+            rects: vec![(0.0, 0.0, 4.0, 10.0)],
+        });
+        assert_eq!(regions[1].bounds, (6.0, 0.0, 10.0, 4.0));
+        assert_eq!(regions[2].bounds, (6.0, 6.0, 10.0, 10.0));
+    }
+
+    #[test]
+    pub fn test_open_regions_merge_columns() {
+        let mut fill_rect = FillRect::from(0.0, 0.0, 10.0, 10.0);
+        fill_rect.fill_rect(2.0, 2.0, 8.0, 8.0);
+        let regions = fill_rect.open_regions(Connectivity::Four);
+        assert_eq!(regions.len(), 1);
+        assert_eq!(regions[0].bounds, (0.0, 0.0, 10.0, 10.0));
+        assert_eq!(regions[0].area, 64.0);
+        // outer ring and the hole around the filled square
+        assert_eq!(regions[0].perimeter, 40.0 + 24.0);
+        assert_eq!(regions[0].rects.len(), 4);
+    }
+
+    #[test]
+    pub fn test_open_regions_corner() {
+        let mut fill_rect = FillRect::from(0.0, 0.0, 2.0, 2.0);
+        fill_rect.fill_rect(0.0, 0.0, 1.0, 1.0);
+        fill_rect.fill_rect(1.0, 1.0, 2.0, 2.0);
+        assert_eq!(fill_rect.open_regions(Connectivity::Four).len(), 2);
+        let regions = fill_rect.open_regions(Connectivity::Eight);
+        assert_eq!(regions.len(), 1);
+        assert_eq!(regions[0].area, 2.0);
+        assert_eq!(regions[0].perimeter, 8.0);
+    }
+}
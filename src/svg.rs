@@ -0,0 +1,227 @@
+use super::fill_rect::{FillRect, FillState};
+use super::range_map::RangeMap;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SvgOptions {
+    // pixels per unit
+    pub scale: f64,
+    // draw larger y higher up, like a plot rather than a screen
+    pub flip_y: bool,
+    pub outline_color: String,
+    pub fill_color: String,
+    pub open_color: String,
+    // stroke for the column boundaries, None to leave them out
+    pub column_color: Option<String>,
+    pub stroke_width: f64,
+}
+
+impl Default for SvgOptions {
+    fn default() -> Self {
+        Self {
+            scale: 10.0,
+            flip_y: true,
+            outline_color: String::from("black"),
+            fill_color: String::from("#888888"),
+            open_color: String::from("#ffffff"),
+            column_color: None,
+            stroke_width: 1.0,
+        }
+    }
+}
+
+// Text made safe to put inside a quoted attribute
+fn escape(text: &str) -> String {
+    let mut r = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => r += "&amp;",
+            '<' => r += "&lt;",
+            '>' => r += "&gt;",
+            '"' => r += "&quot;",
+            '\'' => r += "&apos;",
+            _ => r.push(c),
+        }
+    }
+    return r;
+}
+
+fn svg_header(width: f64, height: f64) -> String {
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">\n",
+        width, height, width, height,
+    )
+}
+
+impl FillRect {
+    // Picture of every filled and open cell of the work region with the
+    // outline of the region on top. Cells outside the region are left blank.
+    pub fn to_svg(&self, options: &SvgOptions) -> String {
+        let Some((min_x, min_y, max_x, max_y)) = self.bounds() else {
+            return svg_header(0.0, 0.0) + "</svg>\n";
+        };
+        let scale = options.scale;
+        let width = (max_x - min_x) * scale;
+        let height = (max_y - min_y) * scale;
+        let px = |x: f64| (x - min_x) * scale;
+        let py = |y: f64| if options.flip_y { (max_y - y) * scale } else { (y - min_y) * scale };
+
+        let mut r = svg_header(width, height);
+        let (fill_color, open_color) = (escape(&options.fill_color), escape(&options.open_color));
+        let columns = self.columns();
+        for (local_min_x, local_max_x, cells) in columns.iter() {
+            for (state, local_min_y, local_max_y) in cells.iter() {
+                let color = if *state == FillState::Filled { &fill_color } else { &open_color };
+                r += &format!(
+                    "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>\n",
+                    px(*local_min_x),
+                    py(*local_min_y).min(py(*local_max_y)),
+                    (local_max_x - local_min_x) * scale,
+                    (local_max_y - local_min_y) * scale,
+                    color,
+                );
+            }
+        }
+
+        if let Some(column_color) = &options.column_color {
+            for (local_min_x, _, cells) in columns.iter().skip(1) {
+                let (Some(first), Some(last)) = (cells.first(), cells.last()) else { continue };
+                r += &format!(
+                    "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"{}\" stroke-width=\"{}\"/>\n",
+                    px(*local_min_x), py(first.1),
+                    px(*local_min_x), py(last.2),
+                    escape(column_color), options.stroke_width,
+                );
+            }
+        }
+
+        for polygon in self.region_contours() {
+            let mut path = String::new();
+            for ring in std::iter::once(&polygon.outer).chain(polygon.holes.iter()) {
+                for (i, (x, y)) in ring.iter().enumerate() {
+                    path += &format!("{}{} {} ", if i == 0 { "M" } else { "L" }, px(*x), py(*y));
+                }
+                path += "Z ";
+            }
+            r += &format!(
+                "<path d=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"{}\"/>\n",
+                path.trim_end(), escape(&options.outline_color), options.stroke_width,
+            );
+        }
+        r += "</svg>\n";
+        return r;
+    }
+}
+
+impl<K: PartialOrd + Copy + Into<f64>, V: PartialEq + Clone> RangeMap<K, V> {
+    // One bar, 10 pixels per unit and 20 pixels high, with a rect per range
+    // in the colour color_fn picks for its value.
+    pub fn to_svg_timeline<F: Fn(&V) -> String>(&self, color_fn: F) -> String {
+        let ranges = self.get_ranges();
+        let (Some(first), Some(last)) = (ranges.first(), ranges.last()) else {
+            return svg_header(0.0, 0.0) + "</svg>\n";
+        };
+        let (min, max): (f64, f64) = ((*first).into(), (*last).into());
+        let scale = 10.0;
+        let height = 20.0;
+
+        let mut r = svg_header((max - min) * scale, height);
+        for (value, local_min, local_max) in self.data() {
+            let (local_min, local_max): (f64, f64) = (local_min.into(), local_max.into());
+            r += &format!(
+                "<rect x=\"{}\" y=\"0\" width=\"{}\" height=\"{}\" fill=\"{}\"/>\n",
+                (local_min - min) * scale,
+                (local_max - local_min) * scale,
+                height,
+                escape(&color_fn(&value)),
+            );
+        }
+        r += "</svg>\n";
+        return r;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_fill_rect_svg() {
+        let mut fill_rect = FillRect::from(0.0, 0.0, 2.0, 1.0);
+        fill_rect.fill_rect(1.0, 0.0, 2.0, 0.5);
+        let svg = fill_rect.to_svg(&SvgOptions {
+            column_color: Some(String::from("red")),
+            ..SvgOptions::default()
+        });
+        assert_eq!(svg, "\
+<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"20\" height=\"10\" viewBox=\"0 0 20 10\">
+<rect x=\"0\" y=\"0\" width=\"10\" height=\"10\" fill=\"#ffffff\"/>
+<rect x=\"10\" y=\"5\" width=\"10\" height=\"5\" fill=\"#888888\"/>
+<rect x=\"10\" y=\"0\" width=\"10\" height=\"5\" fill=\"#ffffff\"/>
+<line x1=\"10\" y1=\"10\" x2=\"10\" y2=\"0\" stroke=\"red\" stroke-width=\"1\"/>
+<path d=\"M0 10 L20 10 L20 0 L0 0 Z\" fill=\"none\" stroke=\"black\" stroke-width=\"1\"/>
+</svg>
+");
+    }
+
+    #[test]
+    pub fn test_fill_rect_svg_no_flip() {
+        let mut fill_rect = FillRect::from(0.0, 0.0, 1.0, 1.0);
+        fill_rect.fill_rect(0.0, 0.0, 1.0, 0.5);
+        let svg = fill_rect.to_svg(&SvgOptions {
+            scale: 2.0,
+            flip_y: false,
+            ..SvgOptions::default()
+        });
+        assert!(svg.contains("<rect x=\"0\" y=\"0\" width=\"2\" height=\"1\" fill=\"#888888\"/>"));
+        assert!(svg.contains("<rect x=\"0\" y=\"1\" width=\"2\" height=\"1\" fill=\"#ffffff\"/>"));
+        assert!(!svg.contains("<line"));
+    }
+
+    #[test]
+    pub fn test_timeline_svg() {
+        let mut map = RangeMap::from(0);
+        map.set(1, 0.0, 1.0);
+        map.set(2, 2.0, 3.0);
+        let svg = map.to_svg_timeline(|value| String::from(["white", "red", "blue"][*value]));
+        assert_eq!(svg, "\
+<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"30\" height=\"20\" viewBox=\"0 0 30 20\">
+<rect x=\"0\" y=\"0\" width=\"10\" height=\"20\" fill=\"red\"/>
+<rect x=\"10\" y=\"0\" width=\"10\" height=\"20\" fill=\"white\"/>
+<rect x=\"20\" y=\"0\" width=\"10\" height=\"20\" fill=\"blue\"/>
+</svg>
+");
+        assert_eq!(RangeMap::<f64, usize>::from(0).to_svg_timeline(|_| String::new()), "\
+<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"0\" height=\"0\" viewBox=\"0 0 0 0\">
+</svg>
+");
+    }
+
+    #[test]
+    pub fn test_fill_rect_svg_outline() {
+        // L shaped stock gets an L shaped outline
+        let fill_rect = FillRect::from_polygon(&[
+            (0.0, 0.0), (2.0, 0.0), (2.0, 1.0), (1.0, 1.0), (1.0, 2.0), (0.0, 2.0),
+        ], 0.1);
+        let svg = fill_rect.to_svg(&SvgOptions {
+            flip_y: false,
+            ..SvgOptions::default()
+        });
+        assert!(svg.contains("<path d=\"M0 0 L20 0 L20 10 L10 10 L10 20 L0 20 Z\" fill=\"none\""));
+    }
+
+    #[test]
+    pub fn test_svg_escapes_colors() {
+        let fill_rect = FillRect::from(0.0, 0.0, 1.0, 1.0);
+        let svg = fill_rect.to_svg(&SvgOptions {
+            open_color: String::from("a\"b"),
+            outline_color: String::from("<&>"),
+            ..SvgOptions::default()
+        });
+        assert!(svg.contains("fill=\"a&quot;b\""));
+        assert!(svg.contains("stroke=\"&lt;&amp;&gt;\""));
+
+        let mut map = RangeMap::from(0);
+        map.set(1, 0.0, 1.0);
+        assert!(map.to_svg_timeline(|_| String::from("'")).contains("fill=\"&apos;\""));
+    }
+}
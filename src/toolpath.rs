@@ -0,0 +1,252 @@
+use super::fill_rect::FillRect;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RasterPattern {
+    // every row cut left to right
+    OneWay,
+    // rows alternate direction, stepping over while still cutting
+    ZigZag,
+}
+
+// A polyline the tool follows without lifting
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pass {
+    pub points: Vec<(f64, f64)>,
+}
+
+const EPSILON: f64 = 1e-9;
+
+// Heights of the rows a tool of the given width needs to cover [min_y, max_y],
+// at most stepover apart.
+fn rows(min_y: f64, max_y: f64, tool_width: f64, stepover: f64) -> Vec<f64> {
+    let radius = tool_width / 2.0;
+    if max_y - min_y <= tool_width {
+        return vec![(min_y + max_y) / 2.0];
+    }
+    let (first, last) = (min_y + radius, max_y - radius);
+    let steps = ((last - first) / stepover).ceil() as usize;
+    let mut r = Vec::with_capacity(steps + 1);
+    for step in 0..steps {
+        r.push(first + (last - first) * step as f64 / steps as f64);
+    }
+    r.push(last);
+    return r;
+}
+
+// Rows of one open rect: the rows of the shared lattice that fall inside it,
+// plus a row hugging its bottom or top where the lattice leaves a band the
+// tool would miss. Rects too thin for any lattice row get rows of their own.
+fn rect_rows(lattice: &[f64], min_y: f64, max_y: f64, tool_width: f64, stepover: f64) -> Vec<f64> {
+    let radius = tool_width / 2.0;
+    let mut r: Vec<f64> = lattice.iter().copied().filter(|y| min_y <= *y && *y < max_y).collect();
+    let (Some(lowest), Some(highest)) = (r.first().copied(), r.last().copied()) else {
+        return rows(min_y, max_y, tool_width, stepover);
+    };
+    if lowest - min_y > radius + EPSILON {
+        r.insert(0, min_y + radius);
+    }
+    if max_y - highest > radius + EPSILON {
+        r.push(max_y - radius);
+    }
+    return r;
+}
+
+// Horizontal rows that cover every open rect when swept at the tool width.
+// Every rect takes its rows from one lattice laid over the whole open area,
+// so neighbouring rects share rows and get joined into one pass.
+pub fn rest_machining(
+    fill_rect: &FillRect,
+    tool_width: f64,
+    stepover: f64,
+    pattern: RasterPattern,
+) -> Vec<Pass> {
+    let stepover = stepover.min(tool_width);
+    if stepover.is_nan() || stepover <= 0.0 {
+        return Vec::new();
+    }
+
+    let open_rects = fill_rect.get_open_rects();
+    let (mut min_y, mut max_y) = (f64::INFINITY, f64::NEG_INFINITY);
+    for (_, local_min_y, _, local_max_y) in open_rects.iter() {
+        min_y = min_y.min(*local_min_y);
+        max_y = max_y.max(*local_max_y);
+    }
+    if min_y >= max_y {
+        return Vec::new();
+    }
+    let lattice = rows(min_y, max_y, tool_width, stepover);
+
+    // (y, min_x, max_x)
+    let mut segments = Vec::new();
+    for (min_x, min_y, max_x, max_y) in open_rects {
+        for y in rect_rows(&lattice, min_y, max_y, tool_width, stepover) {
+            segments.push((y, min_x, max_x));
+        }
+    }
+    segments.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+    let mut joined: Vec<(f64, f64, f64)> = Vec::new();
+    for (y, min_x, max_x) in segments {
+        if let Some(last) = joined.last_mut() {
+            if last.0 == y && min_x <= last.2 + EPSILON {
+                last.2 = last.2.max(max_x);
+                continue;
+            }
+        }
+        joined.push((y, min_x, max_x));
+    }
+
+    let mut passes: Vec<Pass> = Vec::new();
+    let mut i = 0;
+    while i < joined.len() {
+        let y = joined[i].0;
+        let mut row_segments: Vec<((f64, f64), (f64, f64))> = Vec::new();
+        while i < joined.len() && joined[i].0 == y {
+            row_segments.push(((joined[i].1, y), (joined[i].2, y)));
+            i += 1;
+        }
+        // rows off the lattice go the same way as the lattice row next to them
+        let row = lattice.partition_point(|lattice_y| *lattice_y < y - stepover / 2.0);
+        if pattern == RasterPattern::ZigZag && row % 2 == 1 {
+            row_segments.reverse();
+            for segment in row_segments.iter_mut() {
+                *segment = (segment.1, segment.0);
+            }
+        }
+
+        for (start, end) in row_segments {
+            // step over from the end of a pass on an earlier row close by
+            let linked = if pattern == RasterPattern::ZigZag {
+                passes.iter_mut().rev().find(|pass| {
+                    let last = pass.points[pass.points.len()-1];
+                    last.1 < start.1 &&
+                        start.1 - last.1 <= stepover + EPSILON &&
+                        (start.0 - last.0).abs() <= stepover + EPSILON
+                })
+            } else {
+                None
+            };
+            match linked {
+                Some(pass) => {
+                    pass.points.push(start);
+                    pass.points.push(end);
+                }
+                None => passes.push(Pass {
+                    points: vec![start, end],
+                }),
+            }
+        }
+    }
+
+    return passes;
+}
+
+// G-code that runs the passes, lifting to safe_z between them
+pub fn passes_to_gcode(passes: &[Pass], safe_z: f64, cut_z: f64, feed: f64) -> String {
+    let mut r = String::from("G21 G90\n");
+    r += &format!("G0 Z{:.4}\n", safe_z);
+    for pass in passes {
+        let Some((x, y)) = pass.points.first() else { continue };
+        r += &format!("G0 X{:.4} Y{:.4}\n", x, y);
+        r += &format!("G1 Z{:.4} F{:.4}\n", cut_z, feed);
+        for (x, y) in pass.points.iter().skip(1) {
+            r += &format!("G1 X{:.4} Y{:.4}\n", x, y);
+        }
+        r += &format!("G0 Z{:.4}\n", safe_z);
+    }
+    return r;
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::FillState;
+
+    #[test]
+    pub fn test_rows() {
+        assert_eq!(rows(0.0, 1.0, 2.0, 1.0), vec![0.5]);
+        assert_eq!(rows(0.0, 4.0, 2.0, 1.0), vec![1.0, 2.0, 3.0]);
+        assert_eq!(rows(0.0, 4.0, 2.0, 0.8), vec![1.0, 1.6666666666666665, 2.333333333333333, 3.0]);
+    }
+
+    #[test]
+    pub fn test_one_way() {
+        let mut fill_rect = FillRect::from(0.0, 0.0, 10.0, 4.0);
+        fill_rect.fill_rect(4.0, 2.0, 6.0, 4.0);
+        let passes = rest_machining(&fill_rect, 2.0, 2.0, RasterPattern::OneWay);
+        assert_eq!(passes, vec![
+            Pass { points: vec![(0.0, 1.0), (10.0, 1.0)] },
+            Pass { points: vec![(0.0, 3.0), (4.0, 3.0)] },
+            Pass { points: vec![(6.0, 3.0), (10.0, 3.0)] },
+        ]);
+    }
+
+    #[test]
+    pub fn test_zig_zag() {
+        let fill_rect = FillRect::from(0.0, 0.0, 10.0, 4.0);
+        let passes = rest_machining(&fill_rect, 2.0, 1.0, RasterPattern::ZigZag);
+        assert_eq!(passes, vec![
+            Pass { points: vec![
+                (0.0, 1.0), (10.0, 1.0),
+                (10.0, 2.0), (0.0, 2.0),
+                (0.0, 3.0), (10.0, 3.0),
+            ] },
+        ]);
+    }
+
+    #[test]
+    pub fn test_notch_keeps_one_pass() {
+        let mut fill_rect = FillRect::from(0.0, 0.0, 10.0, 10.0);
+        fill_rect.fill_rect(3.0, 9.9, 4.0, 10.0);
+        let passes = rest_machining(&fill_rect, 1.0, 0.7, RasterPattern::ZigZag);
+        assert_eq!(passes.len(), 1);
+        assert_eq!(passes[0].points.len(), 2 * 14);
+
+        let passes = rest_machining(&fill_rect, 1.0, 0.7, RasterPattern::OneWay);
+        assert_eq!(passes.len(), 14);
+    }
+
+    #[test]
+    pub fn test_circle_pass_count() {
+        let mut fill_rect = FillRect::from(0.0, 0.0, 10.0, 10.0);
+        fill_rect.fill_circle(5.0, 5.0, 3.0, 0.5);
+        assert_eq!(fill_rect.get_open_rects().len(), 20);
+        // one zig-zag around the circle, one down its left side and the two
+        // rows hugging its top and bottom
+        let passes = rest_machining(&fill_rect, 1.0, 0.7, RasterPattern::ZigZag);
+        assert_eq!(passes.len(), 4);
+    }
+
+    #[test]
+    pub fn test_covers_open_area() {
+        let mut fill_rect = FillRect::from(0.0, 0.0, 10.0, 10.0);
+        fill_rect.fill_circle(5.0, 5.0, 3.0, 0.5);
+        let passes = rest_machining(&fill_rect, 1.0, 0.7, RasterPattern::ZigZag);
+        let original = fill_rect.clone();
+        for pass in passes.iter() {
+            for pair in pass.points.windows(2) {
+                fill_rect.fill_capsule_with_tolerance(pair[0], pair[1], 0.5, 0.01);
+            }
+        }
+        // nothing is left, and what was already cut and the region are untouched
+        assert_eq!(fill_rect.open_area(), 0.0);
+        assert_eq!(fill_rect.bounds(), original.bounds());
+        for (state, min_x, min_y, max_x, max_y) in original.cells() {
+            if state == FillState::Filled {
+                assert_eq!(fill_rect.is_fill(min_x, min_y, max_x, max_y), true);
+            }
+        }
+    }
+
+    #[test]
+    pub fn test_passes_to_gcode() {
+        let gcode = passes_to_gcode(&[Pass { points: vec![(0.0, 1.0), (10.0, 1.0)] }], 5.0, -1.0, 300.0);
+        assert_eq!(gcode, "G21 G90
+G0 Z5.0000
+G0 X0.0000 Y1.0000
+G1 Z-1.0000 F300.0000
+G1 X10.0000 Y1.0000
+G0 Z5.0000
+");
+    }
+}